@@ -50,11 +50,12 @@ bench!{
 
     fn tuple_alice_x3_iter_mut(mut pool) for (TupleAlice) * 3 {
         for mut guard in pool.iter_mut() {
-            *guard.0 = 1;
-            *guard.1 .0 = 2;
-            *guard.1 .1 = 3;
-            *guard.2 = 4;
-            *guard.3 = 5;
+            let alice = guard.lense_mut();
+            *alice.0 = 1;
+            *alice.1 .0 = 2;
+            *alice.1 .1 = 3;
+            *alice.2 = 4;
+            *alice.3 = 5;
         }
     }
 
@@ -66,11 +67,12 @@ bench!{
 
     fn struct_alice_x3_iter_mut(mut pool) for (Alice<IsMut>) * 3 {
         for mut guard in pool.iter_mut() {
-            *guard.a = 1;
-            *guard.bc.0 = 2;
-            *guard.bc.1 = 3;
-            *guard.d = 4;
-            *guard.e = 5;
+            let alice = guard.lense_mut();
+            *alice.a = 1;
+            *alice.bc.0 = 2;
+            *alice.bc.1 = 3;
+            *alice.d = 4;
+            *alice.e = 5;
         }
     }
 
@@ -82,10 +84,8 @@ bench!{
 
     fn u64_8k_iter_mut(mut pool) for (U64x32x32) * 8 {
         for mut guard in pool.iter_mut() {
-            for i in 0..32 {
-                for j in 0..32 {
-                    *guard[i][j] = 1u64;
-                }
+            for word in guard.lense_mut().iter_mut().flatten() {
+                **word = 1u64;
             }
         }
     }
@@ -98,10 +98,8 @@ bench!{
 
     fn u64_64k_iter_mut(mut pool) for (U64x32x32) * 16 {
         for mut guard in pool.iter_mut() {
-            for i in 0..32 {
-                for j in 0..32 {
-                    *guard[i][j] = 1u64;
-                }
+            for word in guard.lense_mut().iter_mut().flatten() {
+                **word = 1u64;
             }
         }
     }
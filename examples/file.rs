@@ -14,15 +14,15 @@ mk_lense_struct!{
         e:  u64,       // 8
 } // 1 + 3 + 4 + 8 = 16
 
-impl ::std::fmt::Debug for Alice<IsRef> {
+impl<'a> ::std::fmt::Debug for Alice<'a, IsRef> {
     fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
         write!(f, "a: {:?}, b: {:?}, c: {:?}, d: {:?}, e: {:?}",
             self.a, self.bc.0, self.bc.1, self.d, self.e)
     }
 }
 
-impl Alice<IsMut> {
-    fn new(&mut self, a: u8, (b, c): (u8, u16), d: u32, e: u64) {
+impl<'a> Alice<'a, IsMut> {
+    fn set(&mut self, a: u8, (b, c): (u8, u16), d: u32, e: u64) {
         *self.a = a;
         *self.bc.0 = b;
         *self.bc.1 = c;
@@ -31,7 +31,7 @@ impl Alice<IsMut> {
     }
 }
 
-//// This lense can also be represented as a simple tuple.
+// This lense can also be represented as a simple tuple.
 // type Alice = (u8, (u8, u16), u32, u64);
 
 // ~lense.git $ hexdump -C lense-testing-file.dat
@@ -58,12 +58,12 @@ fn main() {
         // Skip two entries, then increment the first value of the third by 5.
         let mut it = lf.iter_mut().skip(2);
         if let Some(mut guard) = it.next() {
-            *guard.a += 5;
+            *guard.lense_mut().a += 5;
         }
 
         // Create the 4th entry manually.
         if let Some(mut guard) = it.next() {
-            guard.new(2, (4, 8), 16, 32);
+            guard.lense_mut().set(2, (4, 8), 16, 32);
         }
     }
 
@@ -72,7 +72,7 @@ fn main() {
         // The guard locks the current index because we currently own the access.
 
         // Dump all values directly to stdout.
-        println!("{:?}", *guard);
+        println!("{:?}", guard.lense());
 
         // The guard is dropped and the current index is unlocked.
     }
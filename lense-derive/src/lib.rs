@@ -0,0 +1,347 @@
+//! `#[derive(Lense)]` for ordinary structs and enums
+//!
+//! The derive generates a `<Name>Lense<M>` view through `mk_lense_struct!` and implements
+//! `Lense` and `LenseMut` for `Name` itself, so `SeekablePool<Name>` hands out guards over the
+//! view.
+//!
+//! ```
+//! #[macro_use] extern crate lense_derive;
+//! extern crate lense;
+//!
+//! #[derive(Lense)]
+//! pub struct Alice {
+//!     pub a: u8,
+//!     #[lense(pad = 1)]
+//!     c: u16,
+//!     #[lense(be)]
+//!     d: u32,
+//!     #[lense(skip)]
+//!     cached: Vec<u8>,
+//! }
+//! # fn main() {
+//! # use lense::Lense;
+//! assert_eq!(Alice::size(), 8);
+//! # }
+//! ```
+//!
+//! `Name` only describes the layout and isn't `lense::Plain`, so its bytes are never read as a
+//! `&Name`:
+//!
+//! ```compile_fail
+//! #[macro_use] extern crate lense_derive;
+//! extern crate lense;
+//!
+//! #[derive(Lense)]
+//! pub struct Packet {
+//!     pub port: u16,
+//!     pub flags: u8,
+//! }
+//! # fn main() {
+//! use lense::DiceRef;
+//!
+//! let buf = [0u8; 4];
+//! let _ = (&mut &buf[..]).dice::<Packet>();
+//! # }
+//! ```
+//!
+//! Field attributes:
+//!
+//! - `#[lense(pad = N)]` reserves `N` bytes before the field
+//! - `#[lense(le)]` and `#[lense(be)]` store the field (or each element of an array or tuple
+//!   field) as `lense::Le<T>` or `lense::Be<T>`
+//! - `#[lense(skip)]` leaves the field out of the lense entirely
+//!
+//! Enums are tagged with the integer named by their `#[repr]`, a `u8` by default, and keep
+//! their discriminants. Other representations are rejected. Each variant gets a writer named
+//! after it, such as `FrameLense::write_ipv4` for `Frame::Ipv4`.
+//!
+//! ```
+//! #[macro_use] extern crate lense_derive;
+//! extern crate lense;
+//!
+//! #[derive(Lense)]
+//! #[repr(u16)]
+//! pub enum Frame {
+//!     Ipv4([u8; 4]) = 0x0800,
+//!     Arp = 0x0806,
+//! }
+//! # fn main() {
+//! # use lense::Lense;
+//! assert_eq!(Frame::size(), 6);
+//! # }
+//! ```
+//!
+//! ```compile_fail
+//! #[macro_use] extern crate lense_derive;
+//! extern crate lense;
+//!
+//! #[derive(Lense)]
+//! #[repr(i8)]
+//! pub enum Level {
+//!     Low = -1,
+//!     High = 1,
+//! }
+//! # fn main() {}
+//! ```
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use] extern crate quote;
+#[macro_use] extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as Tokens;
+use syn::spanned::Spanned;
+use syn::{Attribute, Data, DeriveInput, Error, Fields, Ident, Result, Type, Visibility};
+
+#[proc_macro_derive(Lense, attributes(lense))]
+pub fn derive_lense(input: TokenStream) -> TokenStream {
+    match syn::parse::<DeriveInput>(input).and_then(|input| expand(&input)) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => compile_errors(err).into(),
+    }
+}
+
+/// `Error::to_compile_error` names `::core`, which 2015 edition crates can't see without
+/// `extern crate core`; the prelude's `compile_error!` works everywhere.
+fn compile_errors(err: Error) -> Tokens {
+    err.into_iter().map(|err| {
+        let message = err.to_string();
+        quote_spanned!{err.span()=> compile_error!(#message); }
+    }).collect()
+}
+
+/// Options collected from `#[lense(..)]` field attributes
+#[derive(Default)]
+struct FieldOpts {
+    pad: usize,
+    order: Option<Ident>,
+    skip: bool,
+}
+
+impl FieldOpts {
+    fn parse(attrs: &[Attribute]) -> Result<Self> {
+        let mut opts = FieldOpts::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("lense")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    opts.skip = true;
+                } else if meta.path.is_ident("pad") {
+                    opts.pad = meta.value()?.parse::<syn::LitInt>()?.base10_parse()?;
+                } else if meta.path.is_ident("le") || meta.path.is_ident("be") {
+                    if opts.order.is_some() {
+                        return Err(meta.error("byte order specified twice"));
+                    }
+                    let order = if meta.path.is_ident("le") { "Le" } else { "Be" };
+                    opts.order = Some(Ident::new(order, meta.path.span()));
+                } else {
+                    return Err(meta.error("expected `pad = N`, `le`, `be` or `skip`"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(opts)
+    }
+
+    /// The field type as it is stored in the buffer
+    fn lense_ty(&self, ty: &Type) -> Type {
+        match self.order {
+            Some(ref order) => wrap_order(ty, order),
+            None => ty.clone(),
+        }
+    }
+}
+
+/// Wrap a primitive, or each primitive within an array or tuple, in `Le`/`Be`
+fn wrap_order(ty: &Type, order: &Ident) -> Type {
+    match *ty {
+        Type::Array(ref array) => {
+            let mut array = array.clone();
+            array.elem = Box::new(wrap_order(&array.elem, order));
+            Type::Array(array)
+        }
+        Type::Tuple(ref tuple) => {
+            let mut tuple = tuple.clone();
+            for elem in tuple.elems.iter_mut() {
+                *elem = wrap_order(elem, order);
+            }
+            Type::Tuple(tuple)
+        }
+        Type::Paren(ref paren) => wrap_order(&paren.elem, order),
+        _ => parse_quote!(::lense::#order<#ty>),
+    }
+}
+
+/// `mk_lense_struct!` only understands `pub` and private items
+fn visibility(vis: &Visibility) -> Tokens {
+    match *vis {
+        Visibility::Inherited => quote!(),
+        _ => quote!(pub),
+    }
+}
+
+fn docs(attrs: &[Attribute]) -> Vec<&Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("doc")).collect()
+}
+
+/// `CamelCase` to `snake_case`, for naming variant writers
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            snake.push('_');
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+/// The tag of an enum: the unsigned integer named by its `#[repr]`, or `u8`
+fn enum_tag(attrs: &[Attribute]) -> Result<Ident> {
+    let mut tag = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            match meta.path.get_ident() {
+                Some(ty) if ["u8", "u16", "u32", "u64"].iter().any(|tag| ty == tag) => {
+                    tag = Some(ty.clone());
+                    Ok(())
+                }
+                _ => Err(meta.error("Lense enums take a `#[repr]` of u8, u16, u32 or u64")),
+            }
+        })?;
+    }
+    Ok(tag.unwrap_or_else(|| Ident::new("u8", proc_macro2::Span::call_site())))
+}
+
+fn expand(input: &DeriveInput) -> Result<Tokens> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "Lense cannot be derived for generic types"));
+    }
+
+    let name = &input.ident;
+    let lense = Ident::new(&format!("{}Lense", name), name.span());
+    let vis = visibility(&input.vis);
+    let doc = format!("Lense over the bytes of `{}`", name);
+
+    let body = match input.data {
+        Data::Struct(ref data) => {
+            let fields = match data.fields {
+                Fields::Named(ref fields) => &fields.named,
+                _ => return Err(Error::new_spanned(name, "Lense requires named struct fields")),
+            };
+
+            let mut body = Tokens::new();
+            let mut pads = 0usize;
+            for field in fields {
+                let opts = FieldOpts::parse(&field.attrs)?;
+                if opts.skip {
+                    if opts.pad > 0 || opts.order.is_some() {
+                        return Err(Error::new_spanned(field, "skipped fields take no other options"));
+                    }
+                    continue;
+                }
+                if opts.pad > 0 {
+                    let pad = Ident::new(&format!("__lense_pad{}", pads), name.span());
+                    let len = opts.pad;
+                    body.extend(quote!{ #[allow(dead_code)] #pad: [u8; #len], });
+                    pads += 1;
+                }
+                let field_docs = docs(&field.attrs);
+                let field_vis = visibility(&field.vis);
+                let field_name = &field.ident;
+                let ty = opts.lense_ty(&field.ty);
+                body.extend(quote!{ #(#field_docs)* #field_vis #field_name: #ty, });
+            }
+            quote!{ #vis struct #lense: #body }
+        }
+        Data::Enum(ref data) => {
+            let tag = enum_tag(&input.attrs)?;
+            let mut body = Tokens::new();
+            for variant in &data.variants {
+                let fields: Vec<&Type> = match variant.fields {
+                    Fields::Unnamed(ref fields) => fields.unnamed.iter().map(|f| &f.ty).collect(),
+                    Fields::Unit => Vec::new(),
+                    Fields::Named(..) => return Err(Error::new_spanned(variant,
+                        "Lense enum variants must hold unnamed fields")),
+                };
+                let opts = FieldOpts::parse(&variant.attrs)?;
+                if opts.skip || opts.pad > 0 {
+                    return Err(Error::new_spanned(variant, "enum variants may only set `le` or `be`"));
+                }
+                let variant_docs = docs(&variant.attrs);
+                let variant_name = &variant.ident;
+                let tys = fields.into_iter().map(|ty| opts.lense_ty(ty));
+                let fields = match variant.fields {
+                    Fields::Unit => quote!(),
+                    _ => quote!{ (#(#tys),*) },
+                };
+                let disc = variant.discriminant.as_ref().map(|(_, disc)| quote!{ = #disc });
+                let writer = Ident::new(&format!("write_{}", snake_case(&variant_name.to_string())),
+                                        variant_name.span());
+                body.extend(quote!{
+                    #(#variant_docs)* #variant_name #fields #disc => #writer,
+                });
+            }
+            quote!{ #vis enum #lense(#tag): #body }
+        }
+        Data::Union(..) => return Err(Error::new_spanned(name, "Lense cannot be derived for unions")),
+    };
+
+    Ok(quote!{
+        ::lense::mk_lense_struct!{
+            #[doc = #doc]
+            #body
+        }
+
+        impl ::lense::Lense for #name {
+            type Ref<'a> = <#lense<'static, ::lense::IsRef> as ::lense::Lense>::Ref<'a>;
+
+            const SIZE: usize = <#lense<'static, ::lense::IsRef> as ::lense::Lense>::SIZE;
+            const ALIGN: usize = <#lense<'static, ::lense::IsRef> as ::lense::Lense>::ALIGN;
+            const FIXED_SIZE: bool =
+                <#lense<'static, ::lense::IsRef> as ::lense::Lense>::FIXED_SIZE;
+
+            #[inline]
+            fn try_lense<'a, Buf: ::lense::DiceRef<'a>>(buf: &mut Buf)
+                -> ::std::result::Result<Self::Ref<'a>, ::lense::LenseError>
+            {
+                <#lense<'static, ::lense::IsRef> as ::lense::Lense>::try_lense(buf)
+            }
+
+            #[inline]
+            fn try_lense_record<'a, Buf: ::lense::DiceRef<'a>>(buf: &mut Buf)
+                -> ::std::result::Result<Self::Ref<'a>, ::lense::LenseError>
+            {
+                <#lense<'static, ::lense::IsRef> as ::lense::Lense>::try_lense_record(buf)
+            }
+
+            #[inline]
+            fn lense<'a, Buf: ::lense::DiceRef<'a>>(buf: &mut Buf) -> Self::Ref<'a> {
+                <#lense<'static, ::lense::IsRef> as ::lense::Lense>::lense(buf)
+            }
+        }
+
+        impl ::lense::LenseMut for #name {
+            type Mut<'a> = <#lense<'static, ::lense::IsMut> as ::lense::LenseMut>::Mut<'a>;
+
+            #[inline]
+            fn try_lense_mut<'a, Buf: ::lense::DiceMut<'a>>(buf: &mut Buf)
+                -> ::std::result::Result<Self::Mut<'a>, ::lense::LenseError>
+            {
+                <#lense<'static, ::lense::IsMut> as ::lense::LenseMut>::try_lense_mut(buf)
+            }
+
+            #[inline]
+            fn try_lense_record_mut<'a, Buf: ::lense::DiceMut<'a>>(buf: &mut Buf)
+                -> ::std::result::Result<Self::Mut<'a>, ::lense::LenseError>
+            {
+                <#lense<'static, ::lense::IsMut> as ::lense::LenseMut>::try_lense_record_mut(buf)
+            }
+
+            #[inline]
+            fn lense_mut<'a, Buf: ::lense::DiceMut<'a>>(buf: &mut Buf) -> Self::Mut<'a> {
+                <#lense<'static, ::lense::IsMut> as ::lense::LenseMut>::lense_mut(buf)
+            }
+        }
+    })
+}
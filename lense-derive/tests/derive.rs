@@ -0,0 +1,137 @@
+#![allow(dead_code)]
+
+#[macro_use] extern crate lense_derive;
+extern crate lense;
+
+use lense::{Lense, SeekablePool};
+
+/// Documentation passes through to the generated `AliceLense`
+#[derive(Lense)]
+pub struct Alice {
+    pub a: u8,
+    /// Field documentation is kept
+    pub b: u8,
+    pub c: u16,
+    d: u32,
+    e: u64,
+}
+
+#[derive(Lense)]
+struct Packet {
+    kind: u8,
+    #[lense(pad = 1)]
+    #[lense(be)]
+    port: u16,
+    #[lense(be)]
+    addr: [u8; 4],
+    #[lense(le)]
+    seq: (u16, u16),
+    #[lense(skip)]
+    label: String,
+}
+
+#[derive(Lense)]
+enum Cmd {
+    Get(u8),
+    Set((u8, u8)),
+    #[lense(be)]
+    Move(i8, i8),
+    Stop,
+}
+
+#[derive(Lense)]
+#[repr(u16)]
+enum Status {
+    Idle = 1,
+    Busy(u16) = 0x100,
+    Done,
+}
+
+#[test]
+fn size_matches_mk_lense_struct() {
+    assert_eq!(Alice::size(), 16);
+    assert_eq!(<AliceLense<lense::IsRef>>::size(), 16);
+    assert_eq!(Packet::size(), 12);
+}
+
+#[test]
+fn pool_of_derived_struct() {
+    let mut pool = SeekablePool::<Alice>::with_capacity(2);
+    for mut guard in pool.iter_mut() {
+        let alice = guard.lense_mut();
+        *alice.a = 1;
+        *alice.c = 2;
+        *alice.d = 3;
+    }
+    for guard in pool.iter() {
+        let AliceLense { a, b, c, d, e } = guard.lense();
+        assert_eq!((*a, *b, *c, *d, *e), (1, 0, 2, 3, 0));
+    }
+}
+
+#[test]
+fn attributes() {
+    let mut pool = SeekablePool::<Packet>::with_capacity(1);
+    for mut guard in pool.iter_mut() {
+        let packet = guard.lense_mut();
+        *packet.kind = 7;
+        packet.port.set(0x1f90);
+        packet.addr[0].set(10);
+        packet.seq.1.set(0x0102);
+    }
+    assert_eq!(pool.as_bytes().lense(), &[7, 0, 0x1f, 0x90, 10, 0, 0, 0, 0, 0, 2, 1]);
+}
+
+#[test]
+fn enum_variants() {
+    let pool = SeekablePool::<Cmd>::with_capacity(1);
+    if let CmdLense::Set((a, b)) = CmdLense::write_set(&mut pool.get_mut(0)) {
+        *a = 2;
+        *b = 3;
+    }
+    let bytes = pool.as_bytes();
+    match Cmd::lense(&mut bytes.lense()) {
+        CmdLense::Set((a, b)) => assert_eq!((*a, *b), (2, 3)),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn enum_multi_field_variants() {
+    let pool = SeekablePool::<Cmd>::with_capacity(2);
+    assert_eq!(pool.as_bytes().lense().len(), 2 * Cmd::SIZE);
+    match CmdLense::write_move(&mut pool.get_mut(0)) {
+        CmdLense::Move(x, y) => {
+            x.set(-1);
+            y.set(2);
+        }
+        _ => unreachable!(),
+    }
+    match pool.get(0).lense() {
+        CmdLense::Move(x, y) => assert_eq!((x.get(), y.get()), (-1, 2)),
+        _ => unreachable!(),
+    }
+    CmdLense::write_stop(&mut pool.get_mut(1));
+    assert!(matches!(pool.get(1).lense(), CmdLense::Stop));
+}
+
+#[test]
+fn enum_repr_and_discriminants() {
+    assert_eq!(Status::size(), 4);
+    let mut pool = SeekablePool::<Status>::with_capacity(1);
+    pool.as_bytes_mut()[..2].copy_from_slice(&0x101u16.to_ne_bytes());
+    assert!(matches!(pool.get(0).lense(), StatusLense::Done));
+    pool.as_bytes_mut()[..2].copy_from_slice(&1u16.to_ne_bytes());
+    assert!(matches!(pool.get(0).lense(), StatusLense::Idle));
+}
+
+#[test]
+fn enum_unknown_tag() {
+    let mut pool = SeekablePool::<Status>::with_capacity(1);
+    assert!(matches!(pool.get(0).lense(), StatusLense::InvalidLense));
+    assert!(matches!(pool.get_mut(0).lense_mut(), StatusLense::InvalidLense));
+    pool.as_bytes_mut()[..2].copy_from_slice(&9u16.to_ne_bytes());
+    assert!(matches!(pool.get(0).lense(), StatusLense::InvalidLense));
+    assert_eq!(Status::try_lense(&mut pool.as_bytes().lense()).err(),
+               Some(lense::LenseError::UnknownTag { tag: 9 }));
+}
@@ -1,4 +1,4 @@
-use {DiceRef, DiceMut, Lense};
+use {DiceRef, DiceMut, Lense, LenseError, Plain};
 
 /// Enforce alignment when dicing
 pub struct Aligned<D> {
@@ -6,8 +6,9 @@ pub struct Aligned<D> {
     len: usize,
 }
 
-impl<D: DiceRef> Aligned<D> {
+impl<D> Aligned<D> {
     #[cfg(not(feature = "automatic_padding"))]
+    #[allow(clippy::new_ret_no_self)]
     /// Automatic padding is disabled; ignore Aligned and just use the raw Dice.
     pub fn new(b: D) -> D {
         b
@@ -19,30 +20,20 @@ impl<D: DiceRef> Aligned<D> {
         Aligned { state: b, len: 0 }
     }
 
-    fn align_to(&mut self, size: usize) where D: DiceRef {
-        let offset = self.len % size;
+    fn align_to<'a>(&mut self, align: usize) -> Result<(), LenseError> where D: DiceRef<'a> {
+        let padding = align_up(self.len, align) - self.len;
 
-//      debug_assert!(self.align >= size,
-//          "Pooly ordered struct found. {} > {}, {}", self.align, size, self.len);
-
-        if offset > 0 {
-            debug_assert!(!cfg!(feature = "strict_alignment"),
-                "Poorly aligned struct found. {} % {} = {}", self.len, size, offset);
-
-            self.len += offset;
-
-            // Todo advance the pointer without slicing
-            match offset {
-                1 => { self.dice::<[u8; 1]>(); }
-                2 => { self.dice::<[u8; 2]>(); }
-                3 => { self.dice::<[u8; 3]>(); }
-                4 => { self.dice::<[u8; 4]>(); }
-                5 => { self.dice::<[u8; 5]>(); }
-                6 => { self.dice::<[u8; 6]>(); }
-                7 => { self.dice::<[u8; 7]>(); }
-                _ => panic!("Unimplemented offset correction: {}", offset),
+        if padding > 0 {
+            if cfg!(all(debug_assertions, feature = "strict_alignment")) {
+                panic!("Poorly aligned struct found. {} % {} = {}",
+                       self.len, align, self.len % align);
             }
+
+            self.state.try_skip(padding)?;
+            self.len += padding;
         }
+
+        Ok(())
     }
 
 //  fn waste(&self) -> usize {
@@ -50,18 +41,93 @@ impl<D: DiceRef> Aligned<D> {
 //  }
 }
 
-impl<D: DiceMut> DiceMut for Aligned<D> {
+impl<'a, D: DiceMut<'a>> DiceMut<'a> for Aligned<D> {
+    #[inline]
+    fn try_dice_mut<L: Plain>(&mut self) -> Result<&'a mut L, LenseError> {
+        self.align_to(L::ALIGN)?;
+        let lense = self.state.try_dice_mut()?;
+        self.len += L::SIZE;
+        Ok(lense)
+    }
+
     #[inline]
-    fn dice_mut<'a, L: Lense>(&mut self) -> &'a mut L {
-        self.align_to(L::size());
-        self.state.dice_mut()
+    fn try_dice_bytes_mut(&mut self, n: usize) -> Result<&'a mut [u8], LenseError> {
+        let bytes = self.state.try_dice_bytes_mut(n)?;
+        self.len += n;
+        Ok(bytes)
     }
 }
 
-impl<D: DiceRef> DiceRef for Aligned<D> {
+impl<'a, D: DiceRef<'a>> DiceRef<'a> for Aligned<D> {
+    #[inline]
+    fn try_dice<L: Plain>(&mut self) -> Result<&'a L, LenseError> {
+        self.align_to(L::ALIGN)?;
+        let lense = self.state.try_dice()?;
+        self.len += L::SIZE;
+        Ok(lense)
+    }
+
+    #[inline]
+    fn try_skip(&mut self, n: usize) -> Result<(), LenseError> {
+        self.state.try_skip(n)?;
+        self.len += n;
+        Ok(())
+    }
+
+    #[inline]
+    fn try_dice_bytes(&mut self, n: usize) -> Result<&'a [u8], LenseError> {
+        let bytes = self.state.try_dice_bytes(n)?;
+        self.len += n;
+        Ok(bytes)
+    }
+
     #[inline]
-    fn dice<'a, L: Lense>(&mut self) -> &'a L {
-        self.align_to(L::size());
-        self.state.dice()
+    fn pad_to(&mut self, align: usize) -> Result<(), LenseError> {
+        self.align_to(align)
+    }
+}
+
+/// Round `offset` up to a multiple of `align` when automatic padding is enabled.
+pub const fn align_up(offset: usize, align: usize) -> usize {
+    if cfg!(feature = "automatic_padding") && !offset.is_multiple_of(align) {
+        offset + align - offset % align
+    } else {
+        offset
+    }
+}
+
+/// Size of a composite whose `(size, align)` fields are laid out in order, including interior
+/// and trailing padding when automatic padding is enabled. Used by the lense macros.
+pub const fn composite_size(fields: &[(usize, usize)]) -> usize {
+    let mut offset = 0;
+    let mut align = 1;
+    let mut i = 0;
+    while i < fields.len() {
+        let (size, field_align) = fields[i];
+        offset = align_up(offset, field_align) + size;
+        if field_align > align {
+            align = field_align;
+        }
+        i += 1;
+    }
+    align_up(offset, align)
+}
+
+/// Fails to compile once instantiated for a lense that isn't `FIXED_SIZE`. Called wherever
+/// records are laid out every `SIZE` bytes.
+pub fn assert_fixed_size<L: Lense>() {
+    const { assert!(L::FIXED_SIZE, "Variable length lenses can't be laid out every SIZE bytes") }
+}
+
+/// Largest of `values`, or 0 when empty. Used by the lense macros in `const` contexts.
+pub const fn max_of(values: &[usize]) -> usize {
+    let mut max = 0;
+    let mut i = 0;
+    while i < values.len() {
+        if values[i] > max {
+            max = values[i];
+        }
+        i += 1;
     }
+    max
 }
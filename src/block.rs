@@ -0,0 +1,39 @@
+use {DiceRef, DiceMut, Lense, LenseMut, LenseError, Plain};
+
+/// `N` primitives lensed as a single `&[L; N]` instead of an array of `N` references
+///
+/// Prefer `Block<u8, 256>` over `[u8; 256]` for large runs of plain data: dicing it is one
+/// bounds check and yields one reference. Only `Plain` lenses (primitives and `Le`/`Be`) can be
+/// blocked.
+#[repr(transparent)]
+pub struct Block<L, const N: usize>([L; N]);
+
+impl<L, const N: usize> Lense for Block<L, N>
+    where L: 'static + Plain + for<'a> Lense<Ref<'a> = &'a L>
+{
+    type Ref<'a> = &'a [L; N];
+
+    const SIZE: usize = N * L::SIZE;
+    const ALIGN: usize = L::ALIGN;
+
+    #[inline]
+    fn try_lense<'a, Buf: DiceRef<'a>>(buf: &mut Buf) -> Result<Self::Ref<'a>, LenseError> {
+        buf.try_dice::<Self>().map(|block| &block.0)
+    }
+}
+
+impl<L, const N: usize> LenseMut for Block<L, N>
+    where L: 'static + Plain + for<'a> Lense<Ref<'a> = &'a L>
+           + for<'a> LenseMut<Mut<'a> = &'a mut L>
+{
+    type Mut<'a> = &'a mut [L; N];
+
+    #[inline]
+    fn try_lense_mut<'a, Buf: DiceMut<'a>>(buf: &mut Buf) -> Result<Self::Mut<'a>, LenseError> {
+        buf.try_dice_mut::<Self>().map(|block| &mut block.0)
+    }
+}
+
+unsafe impl<L, const N: usize> Plain for Block<L, N>
+    where L: 'static + Plain + for<'a> Lense<Ref<'a> = &'a L>
+{}
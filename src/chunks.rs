@@ -0,0 +1,328 @@
+use std::marker::PhantomData;
+
+use {Lense, LenseMut, LockingIter, Mode, IsRef, IsMut, PoolError, SeekablePool, Guard};
+use {Reborrow, ReborrowMut};
+use aligned::Aligned;
+use seekable::{stride, PoolWord, Records};
+
+/// Consecutive records, such as a group locked by `SeekablePool::chunks` or the items of a
+/// `LenseVec`.
+pub struct Chunk<'a, L> {
+    bytes: &'a [u8],
+    len: usize,
+    // Bytes from one record to the next
+    stride: usize,
+    marker: PhantomData<fn() -> L>,
+}
+
+impl<'a, L: Lense> Chunk<'a, L> {
+    pub(crate) fn new(bytes: &'a [u8], len: usize, stride: usize) -> Self {
+        Chunk { bytes, len, stride, marker: PhantomData }
+    }
+
+    /// Number of records in the chunk
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// True when the chunk holds no records
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Lense the `i`th record of the chunk
+    pub fn get(&self, i: usize) -> Option<<L as Mode<'_, IsRef>>::Return> {
+        if i < self.len {
+            Some(L::lense(&mut Aligned::new(&self.bytes[i * self.stride..])))
+        } else { None }
+    }
+
+    /// Lense each record of the chunk in turn
+    pub fn iter(&self) -> impl Iterator<Item = <L as Mode<'_, IsRef>>::Return> + '_ {
+        let (mut bytes, stride) = (self.bytes, self.stride);
+        (0..self.len).map(move |_| {
+            let (record, rest) = bytes.split_at(stride);
+            bytes = rest;
+            L::lense(&mut Aligned::new(record))
+        })
+    }
+
+    /// The raw bytes of the chunk
+    pub fn as_bytes(&self) -> &[u8] {
+        self.bytes
+    }
+
+    /// View a chunk of primitives as a plain slice. Panics when the records are padded apart.
+    pub fn as_slice(&self) -> &[L] where L: 'static + for<'b> Lense<Ref<'b> = &'b L> {
+        assert_eq!(self.stride, L::SIZE, "Records are padded apart");
+        match self.len {
+            0 => &[],
+            len => unsafe { // &[u8] -> &[L]
+                ::std::slice::from_raw_parts(self.bytes.as_ptr() as *const L, len)
+            },
+        }
+    }
+}
+
+impl<'a, L: Lense> Reborrow for Chunk<'a, L> {
+    type Ref<'g> = Chunk<'g, L> where Self: 'g;
+
+    fn reborrow(&self) -> Self::Ref<'_> {
+        Chunk::new(self.bytes, self.len, self.stride)
+    }
+}
+
+/// Consecutive records lensed mutably, such as a group locked by `SeekablePool::chunks_mut` or
+/// the items of a `LenseVec`.
+pub struct ChunkMut<'a, L> {
+    bytes: &'a mut [u8],
+    len: usize,
+    // Bytes from one record to the next
+    stride: usize,
+    marker: PhantomData<fn() -> L>,
+}
+
+impl<'a, L: LenseMut> ChunkMut<'a, L> {
+    pub(crate) fn new(bytes: &'a mut [u8], len: usize, stride: usize) -> Self {
+        ChunkMut { bytes, len, stride, marker: PhantomData }
+    }
+
+    /// Number of records in the chunk
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// True when the chunk holds no records
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Lense the `i`th record of the chunk
+    pub fn get<'b>(&'b self, i: usize) -> Option<<L as Mode<'b, IsRef>>::Return> {
+        if i < self.len {
+            Some(L::lense(&mut Aligned::new(&self.bytes[i * self.stride..])))
+        } else { None }
+    }
+
+    /// Lense the `i`th record of the chunk mutably
+    pub fn get_mut<'b>(&'b mut self, i: usize) -> Option<<L as Mode<'b, IsMut>>::Return> {
+        if i < self.len {
+            Some(L::lense_mut(&mut Aligned::new(&mut self.bytes[i * self.stride..])))
+        } else { None }
+    }
+
+    /// Lense each record of the chunk mutably in turn
+    pub fn iter_mut<'b>(&'b mut self)
+        -> impl Iterator<Item = <L as Mode<'b, IsMut>>::Return> + 'b where L: 'b
+    {
+        let (mut bytes, stride) = (&mut *self.bytes, self.stride);
+        (0..self.len).map(move |_| {
+            let (record, rest) = ::std::mem::take(&mut bytes).split_at_mut(stride);
+            bytes = rest;
+            L::lense_mut(&mut Aligned::new(record))
+        })
+    }
+
+    /// The raw bytes of the chunk
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self.bytes
+    }
+
+    /// View a chunk of primitives as a plain mutable slice. Panics when the records are padded
+    /// apart.
+    pub fn as_mut_slice(&mut self) -> &mut [L]
+        where L: 'static + for<'b> LenseMut<Mut<'b> = &'b mut L>
+    {
+        assert_eq!(self.stride, L::SIZE, "Records are padded apart");
+        match self.len {
+            0 => &mut [],
+            len => unsafe { // &mut [u8] -> &mut [L]
+                ::std::slice::from_raw_parts_mut(self.bytes.as_mut_ptr() as *mut L, len)
+            },
+        }
+    }
+}
+
+impl<'a, L: LenseMut> Reborrow for ChunkMut<'a, L> {
+    type Ref<'g> = Chunk<'g, L> where Self: 'g;
+
+    fn reborrow(&self) -> Self::Ref<'_> {
+        Chunk::new(self.bytes, self.len, self.stride)
+    }
+}
+
+impl<'a, L: LenseMut> ReborrowMut for ChunkMut<'a, L> {
+    type Mut<'g> = ChunkMut<'g, L> where Self: 'g;
+
+    fn reborrow_mut(&mut self) -> Self::Mut<'_> {
+        ChunkMut::new(self.bytes, self.len, self.stride)
+    }
+}
+
+impl<L: Lense, A: PoolWord> SeekablePool<L, A> {
+    /// Iterate immutably over groups of `size` records, each locked under one guard. The last
+    /// chunk is shorter when `size` doesn't divide the pool. Panics when `size` is 0.
+    pub fn chunks(&self, size: usize) -> Chunks<'_, L> {
+        assert!(size != 0, "Chunk size must be non-zero");
+        Chunks { records: self.records(), cur: 0, size }
+    }
+
+    /// Iterate mutably over groups of `size` records, each locked under one guard. The last
+    /// chunk is shorter when `size` doesn't divide the pool. Panics when `size` is 0.
+    pub fn chunks_mut(&mut self, size: usize) -> ChunksMut<'_, L> where L: LenseMut {
+        assert!(size != 0, "Chunk size must be non-zero");
+        ChunksMut { records: self.records(), cur: 0, size }
+    }
+
+    /// Iterate immutably over every run of `size` consecutive records, each locked under one
+    /// guard. Panics when `size` is 0.
+    pub fn windows(&self, size: usize) -> Windows<'_, L> {
+        assert!(size != 0, "Window size must be non-zero");
+        Windows { records: self.records(), cur: 0, size }
+    }
+}
+
+macro_rules! mk_chunk_iter {
+    ($(#[$attr:meta])* $iter:ident: $lense:ident, $chunk:ident, $get:ident, $full:expr, $step:expr,
+     $left:expr) => {
+        $(#[$attr])*
+        pub struct $iter<'a, L: 'a + $lense> {
+            records: Records<'a, L>,
+            cur: usize,
+            size: usize,
+        }
+
+        impl<'a, L: $lense> Iterator for $iter<'a, L> {
+            type Item = Guard<'a, $chunk<'a, L>>;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                self.next_indexed().map(|(_, guard)| guard)
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                // Iteration stops early at a locked slot
+                (0, Some($left(self.records.len() - self.cur, self.size)))
+            }
+        }
+
+        impl<'a, L: $lense> LockingIter for $iter<'a, L> {
+            type Guard = Guard<'a, $chunk<'a, L>>;
+
+            fn next_slot(&mut self) -> Option<(usize, Result<Self::Guard, PoolError>)> {
+                let len = self.records.len();
+                let end = ::std::cmp::min(self.cur.saturating_add(self.size), len);
+                // Windows are always full; the last chunk may be short
+                if self.cur < end && (!$full || end - self.cur == self.size) {
+                    let pos = self.cur;
+                    self.cur += $step(end - pos);
+                    Some((pos, self.records.$get(pos..end)))
+                } else { None }
+            }
+
+            fn next_indexed(&mut self) -> Option<(usize, Self::Item)> {
+                match self.next_slot() {
+                    Some((pos, Ok(guard))) => Some((pos, guard)),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+mk_chunk_iter!{
+    /// Iterate immutably over groups of records. See `SeekablePool::chunks`.
+    Chunks: Lense, Chunk, try_get_chunk, false, |n| n,
+    |left: usize, size| left.div_ceil(size)
+}
+mk_chunk_iter!{
+    /// Iterate mutably over groups of records. See `SeekablePool::chunks_mut`.
+    ChunksMut: LenseMut, ChunkMut, try_get_chunk_mut, false, |n| n,
+    |left: usize, size| left.div_ceil(size)
+}
+mk_chunk_iter!{
+    /// Iterate immutably over overlapping runs of records. See `SeekablePool::windows`.
+    Windows: Lense, Chunk, try_get_chunk, true, |_| 1,
+    |left: usize, size| (left + 1).saturating_sub(size)
+}
+
+/// A disjoint run of a pool's records, borrowed mutably without locking. Sub-pools may be sent
+/// to other threads. See `SeekablePool::split_at_mut`.
+///
+/// Dereferences to `ChunkMut` for access to the records.
+pub struct SubPool<'a, L> {
+    start: usize,
+    chunk: ChunkMut<'a, L>,
+}
+
+impl<'a, L: LenseMut> SubPool<'a, L> {
+    /// Position of the first record within the parent pool
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Divide into the records before `mid` and those from `mid` onwards. Panics when `mid` is
+    /// out of bounds.
+    pub fn split_at_mut(self, mid: usize) -> (SubPool<'a, L>, SubPool<'a, L>) {
+        let len = self.chunk.len;
+        assert!(mid <= len, "Invalid index! {} is out of bounds for a pool of {}", mid, len);
+        let stride = self.chunk.stride;
+        let (head, tail) = self.chunk.bytes.split_at_mut(mid * stride);
+        (SubPool { start: self.start, chunk: ChunkMut::new(head, mid, stride) },
+         SubPool { start: self.start + mid, chunk: ChunkMut::new(tail, len - mid, stride) })
+    }
+}
+
+impl<'a, L> ::std::ops::Deref for SubPool<'a, L> {
+    type Target = ChunkMut<'a, L>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.chunk
+    }
+}
+
+impl<'a, L> ::std::ops::DerefMut for SubPool<'a, L> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.chunk
+    }
+}
+
+impl<L: LenseMut, A: PoolWord> SeekablePool<L, A> {
+    /// Divide the pool into the records before `mid` and those from `mid` onwards. Panics when
+    /// `mid` is out of bounds.
+    pub fn split_at_mut(&mut self, mid: usize) -> (SubPool<'_, L>, SubPool<'_, L>) {
+        let len = self.len();
+        let chunk = ChunkMut::new(self.as_bytes_mut(), len, stride::<L, A>());
+        SubPool { start: 0, chunk }.split_at_mut(mid)
+    }
+
+    /// Divide the pool into sub-pools of exactly `size` records. Records left over at the end
+    /// are not yielded. Panics when `size` is 0.
+    pub fn chunks_exact_mut(&mut self, size: usize) -> impl Iterator<Item = SubPool<'_, L>> {
+        assert!(size != 0, "Chunk size must be non-zero");
+        let stride = stride::<L, A>();
+        self.as_bytes_mut().chunks_exact_mut(size * stride).enumerate().map(move |(i, bytes)| {
+            SubPool { start: i * size, chunk: ChunkMut::new(bytes, size, stride) }
+        })
+    }
+
+    /// Call `f` with the position and mutable lense of every record, spreading the pool across
+    /// `threads` scoped threads.
+    pub fn par_iter_mut<F>(&mut self, threads: usize, f: F)
+        where F: for<'b> Fn(usize, <L as Mode<'b, IsMut>>::Return) + Sync
+    {
+        let per_thread = ::std::cmp::max(self.len().div_ceil(::std::cmp::max(threads, 1)), 1);
+        let f = &f;
+        let (_, mut rest) = self.split_at_mut(0);
+        ::std::thread::scope(|s| while !rest.is_empty() {
+            let mid = ::std::cmp::min(per_thread, rest.len());
+            let (mut head, tail) = rest.split_at_mut(mid);
+            rest = tail;
+            s.spawn(move || {
+                let start = head.start();
+                for (i, lense) in head.iter_mut().enumerate() {
+                    f(start + i, lense);
+                }
+            });
+        });
+    }
+}
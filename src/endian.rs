@@ -0,0 +1,93 @@
+use std::fmt;
+
+use {DiceRef, DiceMut, Lense, LenseMut, LenseError, Plain};
+
+/// The bits a primitive is stored as while its bytes are swapped
+///
+/// Floats are kept as integers until read: a swapped float is just bits, and may not survive
+/// being moved around as one.
+#[doc(hidden)]
+pub trait Bits {
+    type Bits: Copy + Default;
+}
+
+/// A primitive stored in little endian byte order
+#[repr(transparent)]
+#[derive(Clone, Copy, Default)]
+pub struct Le<T: Bits>(T::Bits);
+
+/// A primitive stored in big endian byte order
+#[repr(transparent)]
+#[derive(Clone, Copy, Default)]
+pub struct Be<T: Bits>(T::Bits);
+
+macro_rules! mk_endian {
+    (@bits $ty:ident as $bits:ident) => {
+        impl Bits for $ty {
+            type Bits = $bits;
+        }
+    };
+
+    (@impl $wrap:ident $name:expr, $ty:ident, $get:expr, $set:expr) => {
+        impl $wrap<$ty> {
+            /// Store a native value in this byte order
+            #[inline]
+            pub fn new(v: $ty) -> Self {
+                $wrap($set(v))
+            }
+
+            /// Read the value in native byte order
+            #[inline]
+            pub fn get(&self) -> $ty {
+                $get(self.0)
+            }
+
+            /// Overwrite the value from native byte order
+            #[inline]
+            pub fn set(&mut self, v: $ty) {
+                self.0 = $set(v);
+            }
+        }
+
+        impl fmt::Debug for $wrap<$ty> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.debug_tuple($name).field(&self.get()).finish()
+            }
+        }
+    };
+
+    (int $($ty:ident)*) => {$(
+        mk_endian!{ @bits $ty as $ty }
+        mk_endian!{ @impl Le "Le", $ty, $ty::from_le, $ty::to_le }
+        mk_endian!{ @impl Be "Be", $ty, $ty::from_be, $ty::to_be }
+    )*};
+
+    (float $($ty:ident as $bits:ident)*) => {$(
+        mk_endian!{ @bits $ty as $bits }
+        mk_endian!{ @impl Le "Le", $ty,
+            |v: $bits| $ty::from_bits($bits::from_le(v)),
+            |v: $ty| v.to_bits().to_le() }
+        mk_endian!{ @impl Be "Be", $ty,
+            |v: $bits| $ty::from_bits($bits::from_be(v)),
+            |v: $ty| v.to_bits().to_be() }
+    )*};
+}
+
+mk_endian!{int
+     u8  i8
+    u16 i16
+    u32 i32
+    u64 i64
+}
+
+mk_endian!{float
+    f32 as u32
+    f64 as u64
+}
+
+mk_lense_ty!{prim
+    Le<u8>  Le<i8>  Be<u8>  Be<i8>
+    Le<u16> Le<i16> Be<u16> Be<i16>
+    Le<u32> Le<i32> Le<f32> Be<u32> Be<i32> Be<f32>
+    Le<u64> Le<i64> Le<f64> Be<u64> Be<i64> Be<f64>
+}
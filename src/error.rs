@@ -0,0 +1,106 @@
+use std::error::Error;
+use std::fmt;
+
+/// Reasons a buffer could not be lensed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LenseError {
+    /// The buffer ended before the lense was complete
+    Truncated {
+        /// Bytes required by the lense being diced
+        needed: usize,
+        /// Bytes remaining in the buffer
+        available: usize,
+    },
+    /// The buffer doesn't start, or its records don't fall, on the lense's alignment
+    Misaligned {
+        /// Alignment required by the lense
+        align: usize,
+    },
+    /// The buffer isn't a whole number of records
+    Uneven {
+        /// Bytes per record
+        size: usize,
+        /// Bytes in the buffer
+        len: usize,
+    },
+    /// A string field isn't valid UTF-8
+    InvalidUtf8 {
+        /// Bytes of valid UTF-8 before the error
+        valid_up_to: usize,
+    },
+    /// New contents don't fit the field being overwritten
+    DoesNotFit {
+        /// Bytes the field holds
+        capacity: usize,
+        /// Bytes offered
+        len: usize,
+    },
+    /// An enum tag doesn't match any of its variants
+    UnknownTag {
+        /// The tag in native byte order
+        tag: u64,
+    },
+}
+
+impl fmt::Display for LenseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LenseError::Truncated { needed, available } =>
+                write!(f, "Truncated buffer: needed {} bytes, {} available", needed, available),
+            LenseError::Misaligned { align } =>
+                write!(f, "Misaligned buffer: records need {}-byte alignment", align),
+            LenseError::Uneven { size, len } =>
+                write!(f, "Uneven buffer: {} bytes is not a whole number of {}-byte records",
+                       len, size),
+            LenseError::InvalidUtf8 { valid_up_to } =>
+                write!(f, "Invalid UTF-8 after {} bytes", valid_up_to),
+            LenseError::DoesNotFit { capacity, len } =>
+                write!(f, "{} bytes don't fit a field of {}", len, capacity),
+            LenseError::UnknownTag { tag } => write!(f, "Unknown enum tag {:#x}", tag),
+        }
+    }
+}
+
+impl Error for LenseError {}
+
+/// Reasons a pool slot could not be lensed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolError {
+    /// The position is past the end of the pool
+    OutOfBounds {
+        /// Requested position
+        pos: usize,
+        /// Number of slots in the pool
+        len: usize,
+    },
+    /// The slot at this position is held by another guard
+    Locked(usize),
+    /// The record in the slot doesn't lense
+    Lense(LenseError),
+}
+
+impl fmt::Display for PoolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PoolError::OutOfBounds { pos, len } =>
+                write!(f, "Invalid index! {} is out of bounds for a pool of {}", pos, len),
+            PoolError::Locked(pos) => write!(f, "Slot {} is locked", pos),
+            PoolError::Lense(e) => write!(f, "Invalid record: {}", e),
+        }
+    }
+}
+
+impl Error for PoolError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            PoolError::Lense(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<LenseError> for PoolError {
+    fn from(e: LenseError) -> Self {
+        PoolError::Lense(e)
+    }
+}
@@ -3,11 +3,15 @@ use std::io::{self, Read};
 use std::collections::HashMap;
 
 use {SeekablePool, Lense};
+use seekable::stride;
 
 enum PoolPolicy {
     Strict, // Do not allocate more memory when the pool runs out of storage.
+    Grow,   // Resize the pool to fit whatever is read.
 }
 
+// Not wired up yet, see the notes on caching below
+#[allow(dead_code)]
 #[derive(Clone)]
 enum CacheEntry {
     Locked(usize),   // A mutable lense is active
@@ -17,8 +21,10 @@ enum CacheEntry {
 
 /// A cached file access lense. Suitable for databases.
 pub struct LenseFile<L: Lense> {
+    #[allow(dead_code)]
     file: Option<File>,
     pool: SeekablePool<L>,
+    #[allow(dead_code)]
     cache: HashMap<usize, CacheEntry>,
     policy: PoolPolicy,
 }
@@ -34,10 +40,33 @@ impl<L> LenseFile<L> where L: Lense {
         }
     }
 
+    /// Start with an empty pool that grows to fit the files read into it.
+    pub fn new() -> Self {
+        LenseFile {
+            file: None,
+            pool: SeekablePool::new(),
+            cache: HashMap::new(),
+            policy: PoolPolicy::Grow,
+        }
+    }
+
     /// Read a file directly into the pool.
     pub fn read_file(&mut self, file: &mut File) -> io::Result<usize> {
         match self.policy {
-            PoolPolicy::Strict => file.read(&mut *self),
+            PoolPolicy::Strict => file.read(self.pool.as_bytes_mut()),
+            PoolPolicy::Grow => {
+                let mut bytes = Vec::new();
+                let n = file.read_to_end(&mut bytes)?;
+                let len = match stride::<L, u64>() {
+                    0 if n > 0 => return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                                            "Zero sized records hold no bytes")),
+                    0 => 0,
+                    stride => n.div_ceil(stride),
+                };
+                self.pool.resize(len);
+                self.pool.as_bytes_mut()[..n].copy_from_slice(&bytes);
+                Ok(n)
+            }
         }
     }
 
@@ -76,6 +105,12 @@ impl<L> LenseFile<L> where L: Lense {
 
 }
 
+impl<L: Lense> Default for LenseFile<L> {
+    fn default() -> Self {
+        LenseFile::new()
+    }
+}
+
 impl<L: Lense> ::std::ops::Deref for LenseFile<L> {
     type Target = SeekablePool<L>;
 
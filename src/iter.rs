@@ -0,0 +1,122 @@
+use PoolError;
+
+/// Pool iterators that lock one slot at a time
+///
+/// Plain iteration ends at the first slot that can't be locked, or whose record doesn't lense.
+/// These adapters choose what happens at locked slots instead:
+///
+/// - `skip_locked()` passes over locked slots
+/// - `report_locked()` yields `Err(PoolError::Locked(pos))` for them and carries on, also
+///   yielding `Err(PoolError::Lense(..))` for records that don't lense
+/// - `indexed()` pairs each item with its slot position, like `enumerate` but counting skipped
+///   slots
+///
+/// `SyncPool` iterators wait for locked slots to be released instead of ending.
+pub trait LockingIter: Iterator + Sized {
+    /// The guard held over each slot
+    type Guard;
+
+    /// Try to lock the next slot without waiting, returning its position and the outcome
+    fn next_slot(&mut self) -> Option<(usize, Result<Self::Guard, PoolError>)>;
+
+    /// Like `next`, also returning the position of the slot the item came from
+    fn next_indexed(&mut self) -> Option<(usize, Self::Item)>;
+
+    /// Pass over slots that are locked
+    fn skip_locked(self) -> SkipLocked<Self> {
+        SkipLocked { iter: self }
+    }
+
+    /// Yield `Err(PoolError::Locked(pos))` for slots that are locked
+    fn report_locked(self) -> ReportLocked<Self> {
+        ReportLocked { iter: self }
+    }
+
+    /// Pair each item with the position of its slot
+    fn indexed(self) -> Indexed<Self> {
+        Indexed { iter: self }
+    }
+}
+
+/// Iterate over the slots that aren't locked. See `LockingIter::skip_locked`.
+pub struct SkipLocked<I> {
+    iter: I,
+}
+
+impl<I: LockingIter> Iterator for SkipLocked<I> {
+    type Item = I::Guard;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_indexed().map(|(_, guard)| guard)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+impl<I: LockingIter> LockingIter for SkipLocked<I> {
+    type Guard = I::Guard;
+
+    fn next_slot(&mut self) -> Option<(usize, Result<Self::Guard, PoolError>)> {
+        loop {
+            match self.iter.next_slot() {
+                Some((_, Err(PoolError::Locked(..)))) => (),
+                slot => return slot,
+            }
+        }
+    }
+
+    fn next_indexed(&mut self) -> Option<(usize, Self::Item)> {
+        match self.next_slot() {
+            Some((pos, Ok(guard))) => Some((pos, guard)),
+            _ => None,
+        }
+    }
+}
+
+/// Iterate over every slot, reporting the locked ones. See `LockingIter::report_locked`.
+pub struct ReportLocked<I> {
+    iter: I,
+}
+
+impl<I: LockingIter> Iterator for ReportLocked<I> {
+    type Item = Result<I::Guard, PoolError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_indexed().map(|(_, guard)| guard)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<I: LockingIter> LockingIter for ReportLocked<I> {
+    type Guard = I::Guard;
+
+    fn next_slot(&mut self) -> Option<(usize, Result<Self::Guard, PoolError>)> {
+        self.iter.next_slot()
+    }
+
+    fn next_indexed(&mut self) -> Option<(usize, Self::Item)> {
+        self.next_slot()
+    }
+}
+
+/// Pair each item with the position of its slot. See `LockingIter::indexed`.
+pub struct Indexed<I> {
+    iter: I,
+}
+
+impl<I: LockingIter> Iterator for Indexed<I> {
+    type Item = (usize, I::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next_indexed()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
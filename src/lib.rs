@@ -1,70 +1,281 @@
-#![feature(slice_patterns)]
-
 //! MIT 2015 DarkFox
 
 #[macro_use]
 mod prim;
+#[macro_use]
+mod union;
 mod mode;
+mod reborrow;
 mod file;
 mod seekable;
+mod slice;
+mod sync;
 mod aligned;
+mod block;
+mod chunks;
+mod endian;
+mod iter;
+mod vec;
+mod string;
+mod error;
 pub mod macro_tests;
 
+// The README examples run as doctests. The file example reads records as packed 16 bytes.
+#[cfg(all(doctest, not(feature = "automatic_padding")))]
+#[doc = include_str!("../README.md")]
+struct Readme;
+
+pub use aligned::Aligned;
+pub use block::Block;
+pub use chunks::{Chunk, ChunkMut, Chunks, ChunksMut, SubPool, Windows};
+pub use endian::{Le, Be};
+pub use iter::{LockingIter, SkipLocked, ReportLocked, Indexed};
+pub use vec::{Length, LenseVec};
+pub use string::{FixedStr, LenseBytes, LenseStr, StrMut};
+#[doc(hidden)]
+pub use aligned::{assert_fixed_size, composite_size, max_of};
+#[doc(hidden)]
+pub use prim::discriminants;
+#[doc(hidden)]
+pub use endian::Bits;
+pub use error::{LenseError, PoolError};
 pub use mode::{Mode, IsRef, IsMut};
-pub use seekable::{IterRef, IterMut, SeekablePool};
+pub use reborrow::{Reborrow, ReborrowMut};
+pub use prim::Tag;
+pub use seekable::{Guard, IterRef, IterMut, Projection, SeekablePool};
+pub use seekable::{PoolWord, Align16, Align32, Align64, Align4096};
+pub use slice::{LenseSlice, LenseSliceMut};
+pub use sync::{SyncGuard, SyncIterRef, SyncIterMut, SyncPool};
 pub use file::LenseFile;
 
 /// Return an immutable lense and advance the pointer
-pub trait DiceRef {
-    fn dice<'a, L: Lense>(&mut self) -> &'a L;
+///
+/// Lenses borrow from the buffer they were diced from and cannot outlive it.
+///
+/// ```compile_fail
+/// use lense::Lense;
+///
+/// let stale: &u8 = {
+///     let buf = vec![0u8; 1];
+///     u8::lense(&mut &buf[..])
+/// };
+/// ```
+pub trait DiceRef<'buf> {
+    /// Fails without advancing when the buffer is shorter than `L::SIZE`
+    fn try_dice<L: Plain>(&mut self) -> Result<&'buf L, LenseError>;
+
+    /// Panics when the buffer is shorter than `L::SIZE`
+    #[inline]
+    fn dice<L: Plain>(&mut self) -> &'buf L {
+        self.try_dice().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Advance the pointer by `n` bytes without lensing them
+    fn try_skip(&mut self, n: usize) -> Result<(), LenseError>;
+
+    /// Return the next `n` bytes as a run and advance the pointer past them
+    fn try_dice_bytes(&mut self, n: usize) -> Result<&'buf [u8], LenseError>;
+
+    /// Skip padding so the next lense starts on a multiple of `align`. Only `Aligned` tracks
+    /// offsets; plain buffers ignore this.
+    #[inline]
+    #[allow(unused_variables)]
+    fn pad_to(&mut self, align: usize) -> Result<(), LenseError> {
+        Ok(())
+    }
 }
 
 /// Return a mutable lense and advance the pointer
-pub trait DiceMut: DiceRef {
-    fn dice_mut<'a, L: Lense>(&mut self) -> &'a mut L;
-}
+pub trait DiceMut<'buf>: DiceRef<'buf> {
+    /// Fails without advancing when the buffer is shorter than `L::SIZE`
+    fn try_dice_mut<L: Plain>(&mut self) -> Result<&'buf mut L, LenseError>;
 
-/// Lense primitives need lifetimes
-pub trait RefMut<'a>: Lense {
-    type Ref;
-    type Mut;
+    /// Return the next `n` bytes as a mutable run and advance the pointer past them
+    fn try_dice_bytes_mut(&mut self, n: usize) -> Result<&'buf mut [u8], LenseError>;
+
+    /// Panics when the buffer is shorter than `L::SIZE`
+    #[inline]
+    fn dice_mut<L: Plain>(&mut self) -> &'buf mut L {
+        self.try_dice_mut().unwrap_or_else(|e| panic!("{}", e))
+    }
 }
 
 /// A lense-safe type
-pub trait Lense: Mode<IsRef> {
-    type Ref;
-    fn size() -> usize;
-    fn lense<Buf: DiceRef>(buf: &mut Buf) -> <Self as Mode<IsRef>>::Return;
+pub trait Lense {
+    /// The immutable lense borrowing `'a` of the buffer
+    type Ref<'a>;
+
+    /// Bytes occupied in the buffer
+    const SIZE: usize;
+    /// Alignment required in the buffer
+    const ALIGN: usize;
+    /// False when the lense holds variable length fields such as `LenseVec`, so a record may
+    /// run past `SIZE`. Pools and slices lay records out every `SIZE` bytes and reject these.
+    const FIXED_SIZE: bool = true;
+
+    #[inline]
+    fn size() -> usize {
+        Self::SIZE
+    }
+
+    /// Bytes occupied by the lense at the front of `bytes`. This is `SIZE` unless the lense
+    /// holds variable length fields such as `LenseVec`.
+    fn try_size_at(bytes: &[u8]) -> Result<usize, LenseError> where Self: Sized {
+        let mut rest = bytes;
+        Self::try_lense(&mut Aligned::new(&mut rest))?;
+        Ok(bytes.len() - rest.len())
+    }
+
+    fn try_lense<'a, Buf: DiceRef<'a>>(buf: &mut Buf) -> Result<Self::Ref<'a>, LenseError>;
+
+    /// Lense a record held by a pool or slice. This is `try_lense`, except that lenses may
+    /// still read records yet to be written, as enums do with unknown tags.
+    #[inline]
+    fn try_lense_record<'a, Buf: DiceRef<'a>>(buf: &mut Buf)
+        -> Result<Self::Ref<'a>, LenseError>
+    {
+        Self::try_lense(buf)
+    }
+
+    /// Panics when the buffer is too short, see `try_lense_record`
+    #[inline]
+    fn lense<'a, Buf: DiceRef<'a>>(buf: &mut Buf) -> Self::Ref<'a> {
+        Self::try_lense_record(buf).unwrap_or_else(|e| panic!("{}", e))
+    }
 }
 
 /// A mutable lense-safe type
-pub trait LenseMut: Lense + Mode<IsMut> {
-    type Mut;
-    fn lense_mut<Buf: DiceMut>(buf: &mut Buf) -> <Self as Mode<IsMut>>::Return;
+pub trait LenseMut: Lense {
+    /// The mutable lense borrowing `'a` of the buffer
+    type Mut<'a>;
+
+    fn try_lense_mut<'a, Buf: DiceMut<'a>>(buf: &mut Buf) -> Result<Self::Mut<'a>, LenseError>;
+
+    /// Lense a record held by a pool or slice mutably, see `Lense::try_lense_record`
+    #[inline]
+    fn try_lense_record_mut<'a, Buf: DiceMut<'a>>(buf: &mut Buf)
+        -> Result<Self::Mut<'a>, LenseError>
+    {
+        Self::try_lense_mut(buf)
+    }
+
+    /// Panics when the buffer is too short, see `try_lense_record_mut`
+    #[inline]
+    fn lense_mut<'a, Buf: DiceMut<'a>>(buf: &mut Buf) -> Self::Mut<'a> {
+        Self::try_lense_record_mut(buf).unwrap_or_else(|e| panic!("{}", e))
+    }
 }
 
+/// A lense whose bytes are the value itself, so it can be diced straight out of a buffer
+///
+/// Composite lenses are views over their fields and can't be diced whole:
+///
+/// ```compile_fail
+/// use lense::DiceRef;
+///
+/// let buf = [0u8; 4];
+/// let _ = (&mut &buf[..]).dice::<(u8, u16)>();
+/// ```
+///
+/// # Safety
+///
+/// Every `SIZE` bytes must be a valid `Self`, and `SIZE` and `ALIGN` must be the size and
+/// alignment of `Self`.
+pub unsafe trait Plain: Lense {}
+
 macro_rules! mk_dice {
-    (mut $ty:ty, $x:expr, $split:ident) => {
-        impl<'a> DiceMut for $ty {
-            fn dice_mut<'b, L: Lense>(&mut self) -> &'b mut L {
-                let (head, tail) = ::std::mem::replace(self, $x).$split(L::size());
+    (mut $ty:ty, $split:ident) => {
+        impl<'a> DiceMut<'a> for $ty {
+            #[inline]
+            fn try_dice_mut<L: Plain>(&mut self) -> Result<&'a mut L, LenseError> {
+                if self.len() < L::SIZE {
+                    return Err(LenseError::Truncated { needed: L::SIZE, available: self.len() });
+                }
+                let (head, tail) = ::std::mem::take(self).$split(L::SIZE);
                 *self = tail;
-                unsafe { &mut *(head.as_mut_ptr() as *mut L) }
+                Ok(unsafe { &mut *(head.as_mut_ptr() as *mut L) })
+            }
+
+            #[inline]
+            fn try_dice_bytes_mut(&mut self, n: usize) -> Result<&'a mut [u8], LenseError> {
+                if self.len() < n {
+                    return Err(LenseError::Truncated { needed: n, available: self.len() });
+                }
+                let (head, tail) = ::std::mem::take(self).$split(n);
+                *self = tail;
+                Ok(head)
             }
         }
-        mk_dice!{ $ty, $x, $split }
+        mk_dice!{ $ty, $split }
     };
-    ($ty:ty, $x:expr, $split:ident) => {
-        impl<'a> DiceRef for $ty {
+    ($ty:ty, $split:ident) => {
+        impl<'a> DiceRef<'a> for $ty {
             #[inline]
-            fn dice<'b, L: Lense>(&mut self) -> &'b L {
-                let (head, tail) = ::std::mem::replace(self, $x).$split(L::size());
+            fn try_dice<L: Plain>(&mut self) -> Result<&'a L, LenseError> {
+                if self.len() < L::SIZE {
+                    return Err(LenseError::Truncated { needed: L::SIZE, available: self.len() });
+                }
+                let (head, tail) = ::std::mem::take(self).$split(L::SIZE);
                 *self = tail;
-                unsafe { &*(head.as_ptr() as *const L) }
+                Ok(unsafe { &*(head.as_ptr() as *const L) })
+            }
+
+            #[inline]
+            fn try_skip(&mut self, n: usize) -> Result<(), LenseError> {
+                if self.len() < n {
+                    return Err(LenseError::Truncated { needed: n, available: self.len() });
+                }
+                *self = ::std::mem::take(self).$split(n).1;
+                Ok(())
+            }
+
+            #[inline]
+            fn try_dice_bytes(&mut self, n: usize) -> Result<&'a [u8], LenseError> {
+                if self.len() < n {
+                    return Err(LenseError::Truncated { needed: n, available: self.len() });
+                }
+                let (head, tail) = ::std::mem::take(self).$split(n);
+                *self = tail;
+                Ok(head)
             }
         }
     };
 }
 
-mk_dice!{     &'a     [u8], &[],    split_at }
-mk_dice!{ mut &'a mut [u8], &mut[], split_at_mut }
+mk_dice!{     &'a     [u8], split_at }
+mk_dice!{ mut &'a mut [u8], split_at_mut }
+
+// Lense through a borrowed buffer, leaving what remains of it behind
+impl<'a, D: DiceRef<'a>> DiceRef<'a> for &mut D {
+    #[inline]
+    fn try_dice<L: Plain>(&mut self) -> Result<&'a L, LenseError> {
+        (**self).try_dice()
+    }
+
+    #[inline]
+    fn try_skip(&mut self, n: usize) -> Result<(), LenseError> {
+        (**self).try_skip(n)
+    }
+
+    #[inline]
+    fn try_dice_bytes(&mut self, n: usize) -> Result<&'a [u8], LenseError> {
+        (**self).try_dice_bytes(n)
+    }
+
+    #[inline]
+    fn pad_to(&mut self, align: usize) -> Result<(), LenseError> {
+        (**self).pad_to(align)
+    }
+}
+
+impl<'a, D: DiceMut<'a>> DiceMut<'a> for &mut D {
+    #[inline]
+    fn try_dice_mut<L: Plain>(&mut self) -> Result<&'a mut L, LenseError> {
+        (**self).try_dice_mut()
+    }
+
+    #[inline]
+    fn try_dice_bytes_mut(&mut self, n: usize) -> Result<&'a mut [u8], LenseError> {
+        (**self).try_dice_bytes_mut(n)
+    }
+}
@@ -17,20 +17,19 @@
 //! ```
 //! # #![allow(dead_code)]
 //! # #[macro_use] extern crate lense;
-//! #[cfg(feature = "experimental_lense_enums")]
 //! mk_lense_struct!{pub enum Bob:
 //!     U8(u8),
 //!     /// Documentation is optional
 //!     U16(u16),
 //!     U32(u32),
+//!     Pair(u8, u32),
+//!     Empty,
 //! }
 //! # fn main() {}
 //! ```
 
 #![allow(dead_code)]
 
-use Lense;
-
 mk_lense_struct!{
     pub struct Alice:
         a:  u8,
@@ -40,7 +39,6 @@ mk_lense_struct!{
         e:  u64,
 }
 
-#[cfg(feature = "experimental_lense_enums")]
 mk_lense_struct!{
     /// Documenting things is good
     pub enum Bob:
@@ -48,4 +46,6 @@ mk_lense_struct!{
         /// although, doucmentation is optional
         U16(u16),
         U32(u32),
+        Pair(u8, u32),
+        Empty,
 }
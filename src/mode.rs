@@ -1,13 +1,13 @@
 use {Lense, LenseMut};
 
 /// Handle type level modes
-pub trait Mode<M> { type Return; }
+pub trait Mode<'a, M> { type Return; }
 
 /// Mode: Immutable reference
 pub enum IsRef {}
 /// Mode: Mutable reference
 pub enum IsMut {}
 
-// RefMut implements both type conditions
-impl<E: Lense>    Mode<IsRef> for E { type Return = E::Ref; }
-impl<E: LenseMut> Mode<IsMut> for E { type Return = E::Mut; }
+// Lense and LenseMut implement both type conditions
+impl<'a, E: Lense>    Mode<'a, IsRef> for E { type Return = E::Ref<'a>; }
+impl<'a, E: LenseMut> Mode<'a, IsMut> for E { type Return = E::Mut<'a>; }
@@ -1,123 +1,253 @@
-use {DiceRef, DiceMut, Lense, LenseMut, RefMut, Mode, IsRef, IsMut};
+use {DiceRef, DiceMut, Lense, LenseMut, Plain, Mode, IsRef, IsMut, LenseError, Le, Be};
+use aligned::{composite_size, max_of};
 
 macro_rules! mk_lense_ty {
-    (@void $void:tt $expr:expr) => { $expr };
-
     (tuple $($ty:ident)*) => { mk_lense_ty!{ () void $($ty)* } };
-    (array $($tt:tt)*) => { mk_lense_ty!{ [] $(($tt))* } };
     (prim $($ty:ty)*) => {$(
-        impl<'a> RefMut<'a> for $ty {
-            type Ref = &'a $ty;
-            type Mut = &'a mut $ty;
-        }
+        impl Lense for $ty {
+            type Ref<'a> = &'a $ty;
 
-        impl<'a> Lense for $ty {
-            type Ref = <$ty as RefMut<'a>>::Ref;
-
-            #[inline]
-            fn size() -> usize {
-                ::std::mem::size_of::<Self>()
-            }
+            const SIZE: usize = ::std::mem::size_of::<$ty>();
+            const ALIGN: usize = ::std::mem::align_of::<$ty>();
 
             #[inline]
             #[allow(unused_variables)]
-            fn lense<Buf: DiceRef>(buf: &mut Buf) -> Self::Ref {
-                buf.dice::<Self>()
+            fn try_lense<'a, Buf: DiceRef<'a>>(buf: &mut Buf) -> Result<Self::Ref<'a>, LenseError> {
+                buf.try_dice::<Self>()
             }
         }
 
-        impl<'a> LenseMut for $ty {
-            type Mut = <$ty as RefMut<'a>>::Mut;
+        impl LenseMut for $ty {
+            type Mut<'a> = &'a mut $ty;
 
             #[inline]
             #[allow(unused_variables)]
-            fn lense_mut<Buf: DiceMut>(buf: &mut Buf) -> Self::Mut {
-                buf.dice_mut::<Self>()
+            fn try_lense_mut<'a, Buf: DiceMut<'a>>(buf: &mut Buf)
+                -> Result<Self::Mut<'a>, LenseError>
+            {
+                buf.try_dice_mut::<Self>()
             }
         }
+
+        unsafe impl Plain for $ty {}
     )*};
 
     (()) => { };
     (() $head:tt $($tail:ident)*) => {
         impl<$($tail: Lense),*> Lense for ($($tail,)*) {
-            type Ref = ($(<$tail as Mode<IsRef>>::Return,)*);
+            type Ref<'a> = ($(<$tail as Mode<'a, IsRef>>::Return,)*);
 
-            #[inline]
-            fn size() -> usize {
-                0usize $(+ <$tail>::size())*
-            }
+            const SIZE: usize = composite_size(&[$((<$tail>::SIZE, <$tail>::ALIGN)),*]);
+            const ALIGN: usize = max_of(&[1 $(, <$tail>::ALIGN)*]);
+            const FIXED_SIZE: bool = true $(&& <$tail>::FIXED_SIZE)*;
 
             #[inline]
             #[allow(unused_variables)]
-            fn lense<Buf: DiceRef>(buf: &mut Buf) -> Self::Ref {
-                ($(<$tail>::lense(buf),)*)
+            fn try_lense<'a, Buf: DiceRef<'a>>(buf: &mut Buf) -> Result<Self::Ref<'a>, LenseError> {
+                buf.pad_to(Self::ALIGN)?;
+                let lense = ($(<$tail>::try_lense(buf)?,)*);
+                buf.pad_to(Self::ALIGN)?;
+                Ok(lense)
             }
         }
 
         impl<$($tail: LenseMut),*> LenseMut for ($($tail,)*) {
-            type Mut = ($(<$tail as Mode<IsMut>>::Return,)*);
+            type Mut<'a> = ($(<$tail as Mode<'a, IsMut>>::Return,)*);
 
             #[inline]
             #[allow(unused_variables)]
-            fn lense_mut<Buf: DiceMut>(buf: &mut Buf) -> Self::Mut {
-                ($(<$tail>::lense_mut(buf),)*)
+            fn try_lense_mut<'a, Buf: DiceMut<'a>>(buf: &mut Buf)
+                -> Result<Self::Mut<'a>, LenseError>
+            {
+                buf.pad_to(Self::ALIGN)?;
+                let lense = ($(<$tail>::try_lense_mut(buf)?,)*);
+                buf.pad_to(Self::ALIGN)?;
+                Ok(lense)
             }
         }
         mk_lense_ty!{ () $($tail)* }
     };
+}
+
+mk_lense_ty!{prim
+     u8  i8
+    u16 i16
+    u32 i32 f32
+    u64 i64 f64
+}
+
+mk_lense_ty!{tuple
+    A B C D E F
+    G H I J K L
+}
+
+impl<L: Lense, const N: usize> Lense for [L; N] {
+    type Ref<'a> = [<L as Mode<'a, IsRef>>::Return; N];
+
+    const SIZE: usize = N * L::SIZE;
+    const ALIGN: usize = L::ALIGN;
+    const FIXED_SIZE: bool = L::FIXED_SIZE;
+
+    #[inline]
+    fn try_lense<'a, Buf: DiceRef<'a>>(buf: &mut Buf) -> Result<Self::Ref<'a>, LenseError> {
+        try_array(|| L::try_lense(buf))
+    }
+}
+
+impl<L: LenseMut, const N: usize> LenseMut for [L; N] {
+    type Mut<'a> = [<L as Mode<'a, IsMut>>::Return; N];
+
+    #[inline]
+    fn try_lense_mut<'a, Buf: DiceMut<'a>>(buf: &mut Buf) -> Result<Self::Mut<'a>, LenseError> {
+        try_array(|| L::try_lense_mut(buf))
+    }
+}
+
+/// Build `[T; N]` from `N` calls to `f`, stopping at the first error
+#[inline]
+fn try_array<T, F, const N: usize>(mut f: F) -> Result<[T; N], LenseError>
+    where F: FnMut() -> Result<T, LenseError>
+{
+    let mut err = None;
+    let array = [(); N].map(|()| {
+        if err.is_some() {
+            return None;
+        }
+        match f() {
+            Ok(lense) => Some(lense),
+            Err(e) => {
+                err = Some(e);
+                None
+            }
+        }
+    });
+    match err {
+        Some(e) => Err(e),
+        None => Ok(array.map(|lense| lense.unwrap())),
+    }
+}
 
-    ([]) => { };
-    ([] ($n:expr) $(($m:expr))*) => {
-        impl<L: Lense> Lense for [L; $n] {
-            type Ref = [<L as Mode<IsRef>>::Return; $n];
+
+/// An unsigned integer lense that can tag the variants of a lense enum
+pub trait Tag: 'static + Plain + for<'a> Lense<Ref<'a> = &'a Self>
+                              + for<'a> LenseMut<Mut<'a> = &'a mut Self>
+{
+    /// Largest discriminant the tag can hold
+    const MAX: u64;
+
+    /// The tag in native byte order
+    fn to_tag(&self) -> u64;
+
+    /// Store `tag`, which must not exceed `MAX`
+    fn from_tag(tag: u64) -> Self;
+}
+
+macro_rules! mk_tag {
+    (prim $($ty:ident)*) => {$(
+        impl Tag for $ty {
+            const MAX: u64 = $ty::MAX as u64;
 
             #[inline]
-            fn size() -> usize {
-                $n * L::size()
+            fn to_tag(&self) -> u64 {
+                *self as u64
             }
 
             #[inline]
-            #[allow(unused_variables)]
-            fn lense<Buf: DiceRef>(buf: &mut Buf) -> Self::Ref {
-                [$(mk_lense_ty!{ @void ($m) L::lense(buf) }),*]
+            fn from_tag(tag: u64) -> Self {
+                tag as $ty
             }
         }
+    )*};
+    (endian $($wrap:ident<$ty:ident>)*) => {$(
+        impl Tag for $wrap<$ty> {
+            const MAX: u64 = $ty::MAX as u64;
 
-        impl<L: LenseMut> LenseMut for [L; $n] {
-            type Mut = [<L as Mode<IsMut>>::Return; $n];
+            #[inline]
+            fn to_tag(&self) -> u64 {
+                self.get() as u64
+            }
 
             #[inline]
-            #[allow(unused_variables)]
-            fn lense_mut<Buf: DiceMut>(buf: &mut Buf) -> Self::Mut {
-                [$(mk_lense_ty!{ @void ($m) L::lense_mut(buf) }),*]
+            fn from_tag(tag: u64) -> Self {
+                $wrap::<$ty>::new(tag as $ty)
             }
         }
-        mk_lense_ty!{ [] $(($m))* }
-    };
-}
-
-mk_lense_ty!{prim
-     u8  i8
-    u16 i16
-    u32 i32 f32
-    u64 i64 f64
+    )*};
 }
 
-mk_lense_ty!{tuple
-    A B C D E F
-    G H I J K L
+mk_tag!{prim u8 u16 u32 u64}
+mk_tag!{endian
+    Le<u8> Le<u16> Le<u32> Le<u64>
+    Be<u8> Be<u16> Be<u32> Be<u64>
 }
 
-mk_lense_ty!{array
-    32 31 30 29 28 27 26 25
-    24 23 22 21 20 19 18 17
-    16 15 14 13 12 11 10  9
-     8  7  6  5  4  3  2  1
-     0
+/// Discriminants of an enum's variants: the explicit value where given, otherwise one more than
+/// the previous variant. Panics, failing const evaluation, on duplicates or values above `max`.
+#[doc(hidden)]
+pub const fn discriminants<const N: usize>(explicit: [Option<u64>; N], max: u64) -> [u64; N] {
+    let mut values = [0; N];
+    let mut next = 0;
+    let mut i = 0;
+    while i < N {
+        let value = match explicit[i] {
+            Some(value) => value,
+            None => next,
+        };
+        assert!(value <= max, "Enum discriminant does not fit its tag");
+        let mut j = 0;
+        while j < i {
+            assert!(values[j] != value, "Enum discriminants must be unique");
+            j += 1;
+        }
+        values[i] = value;
+        next = value.wrapping_add(1);
+        i += 1;
+    }
+    values
 }
 
 
-/// Create a lense-safe struct containing lense-safe types (Enums are experimental)
+/// Create a lense-safe struct or enum containing lense-safe types
+///
+/// Enums store a tag followed by the payload of the tagged variant. Payloads start on the enum's
+/// alignment and are padded to the largest variant, so every value of the enum occupies `SIZE`
+/// bytes. The tag is a `u8` unless another `Tag` is named after the enum, as in
+/// `enum Frame(Be<u16>):`. Variants are numbered like Rust enums: from 0, or one more than the
+/// previous variant, unless given an explicit `Variant(..) = value`.
+///
+/// `try_lense` reports unknown tags as `LenseError::UnknownTag`, while `lense` and pools give
+/// them the `InvalidLense` variant so records yet to be written can still be read.
+///
+/// A variant followed by `=> name` gets a writer of that name, which switches the record locked
+/// by a mutable `Guard` to the variant and zeroes its fields.
+///
+/// ```
+/// # #[macro_use] extern crate lense;
+/// # use lense::{Be, Lense, LenseError, IsRef, IsMut, SeekablePool};
+/// mk_lense_struct!{pub enum Frame(Be<u16>):
+///     Ipv4([u8; 4]) = 0x0800,
+///     Arp = 0x0806,
+///     Vlan(Be<u16>) = 0x8100 => write_vlan,
+/// }
+///
+/// # fn main() {
+/// let mut pool = SeekablePool::<Frame<IsMut>>::with_capacity(1);
+/// pool.as_bytes_mut().copy_from_slice(&[0x08, 0x00, 10, 0, 0, 1]);
+/// assert!(matches!(pool.get(0).lense(), Frame::Ipv4([&10, &0, &0, &1])));
+///
+/// let mut guard = pool.get_mut(0);
+/// if let Frame::Vlan(id) = Frame::write_vlan(&mut guard) {
+///     id.set(42);
+/// }
+/// drop(guard);
+/// assert_eq!(pool.as_bytes().lense(), [0x81, 0x00, 0, 42, 0, 0]);
+///
+/// pool.as_bytes_mut()[..2].copy_from_slice(&[0x86, 0xdd]);
+/// assert!(matches!(pool.get(0).lense(), Frame::InvalidLense));
+/// assert_eq!(Frame::<IsRef>::try_lense(&mut pool.as_bytes().lense()).err(),
+///            Some(LenseError::UnknownTag { tag: 0x86dd }));
+/// # }
+/// ```
 #[macro_export]
 macro_rules! mk_lense_struct {
     (@as_item $item:item) => { $item };
@@ -125,13 +255,25 @@ macro_rules! mk_lense_struct {
     // Type independant item parsing
 
     ([$($meta:tt)*] #[$attr:meta] $($tt:tt)*) => {
-        mk_lense_struct!{ [$($meta)* $attr] $($tt)* }
+        $crate::mk_lense_struct!{ [$($meta)* $attr] $($tt)* }
+    };
+    ([$($meta:tt)*] pub enum $ident:ident($tag:ty): $($tt:tt)*) => {
+        $crate::mk_lense_struct!{ @enum public ([$($meta)*] $ident [$tag]) () $($tt)* }
+    };
+    ([$($meta:tt)*] enum $ident:ident($tag:ty): $($tt:tt)*) => {
+        $crate::mk_lense_struct!{ @enum private ([$($meta)*] $ident [$tag]) () $($tt)* }
+    };
+    ([$($meta:tt)*] pub enum $ident:ident: $($tt:tt)*) => {
+        $crate::mk_lense_struct!{ @enum public ([$($meta)*] $ident [u8]) () $($tt)* }
+    };
+    ([$($meta:tt)*] enum $ident:ident: $($tt:tt)*) => {
+        $crate::mk_lense_struct!{ @enum private ([$($meta)*] $ident [u8]) () $($tt)* }
     };
     ([$($meta:tt)*] pub $ty:tt $ident:ident: $($tt:tt)*) => {
-        mk_lense_struct!{ @$ty public ([$($meta)*] $ident) () $($tt)* }
+        $crate::mk_lense_struct!{ @$ty public ([$($meta)*] $ident) () $($tt)* }
     };
     ([$($meta:tt)*] $ty:tt $ident:ident: $($tt:tt)*) => {
-        mk_lense_struct!{ @$ty private ([$($meta)*] $ident) () $($tt)* }
+        $crate::mk_lense_struct!{ @$ty private ([$($meta)*] $ident) () $($tt)* }
     };
 
     // Struct parsing
@@ -139,24 +281,28 @@ macro_rules! mk_lense_struct {
     (@struct public ([$($meta:tt)*] $ident:ident $($builder_struct:tt)*)
                     ($($field:ident: $ty:ty,)*) $(,)*
     ) => {
-        mk_lense_struct!{ @as_item
-            $(#[$meta])* pub struct $ident<M> where $($ty: $crate::Mode<M>),* { $($builder_struct)* }
+        $crate::mk_lense_struct!{ @as_item
+            $(#[$meta])* pub struct $ident<'a, M> where $($ty: $crate::Mode<'a, M>),* {
+                $($builder_struct)*
+            }
         }
-        mk_lense_struct!{ {} $ident $($field: $ty),* }
+        $crate::mk_lense_struct!{ {} $ident $($field: $ty),* }
     };
     (@struct private ([$($meta:tt)*] $ident:ident $($builder_struct:tt)*)
                      ($($field:ident: $ty:ty,)*) $(,)*
     ) => {
-        mk_lense_struct!{ @as_item
-            $(#[$meta])* struct $ident<M> where $($ty: $crate::Mode<M>),* { $($builder_struct)* }
+        $crate::mk_lense_struct!{ @as_item
+            $(#[$meta])* struct $ident<'a, M> where $($ty: $crate::Mode<'a, M>),* {
+                $($builder_struct)*
+            }
         }
-        mk_lense_struct!{ {} $ident $($field: $ty),* }
+        $crate::mk_lense_struct!{ {} $ident $($field: $ty),* }
     };
 
     (@struct $vis:tt ($($builder_struct:tt)*) ($($builder_impl:tt)*)
         #[$attr:meta] $($tt:tt)*
     ) => {
-        mk_lense_struct!{ @struct $vis
+        $crate::mk_lense_struct!{ @struct $vis
             ($($builder_struct)* #[$attr])
             ($($builder_impl)*)
             $($tt)*
@@ -165,7 +311,7 @@ macro_rules! mk_lense_struct {
     (@struct $vis:tt ($($builder_struct:tt)*) ($($builder_impl:tt)*)
         pub $($tt:tt)*
     ) => {
-        mk_lense_struct!{ @struct $vis
+        $crate::mk_lense_struct!{ @struct $vis
             ($($builder_struct)* pub)
             ($($builder_impl)*)
             $($tt)*
@@ -174,8 +320,8 @@ macro_rules! mk_lense_struct {
     (@struct $vis:tt ($($builder_struct:tt)*) ($($builder_impl:tt)*)
         $ident:ident: $ty:ty , $($tt:tt)*
     ) => {
-        mk_lense_struct!{ @struct $vis
-            ($($builder_struct)* $ident: <$ty as $crate::Mode<M>>::Return,)
+        $crate::mk_lense_struct!{ @struct $vis
+            ($($builder_struct)* $ident: <$ty as $crate::Mode<'a, M>>::Return,)
             ($($builder_impl)* $ident: $ty,)
             $($tt)*
         }
@@ -183,44 +329,88 @@ macro_rules! mk_lense_struct {
 
     // Enum parsing
 
-    (@enum public ([$($meta:tt)*] $ident:ident $($builder_struct:tt)*)
-                  ($($field:ident($($ty:ty),*))*) $(,)*
+    (@enum public ([$($meta:tt)*] $ident:ident [$tag:ty] $($builder_struct:tt)*)
+                  ($($variant:ident [$($unit:ident)?] ($($field:ident: $ty:ty),*)
+                     [$($disc:expr)?] [$($writer:ident)?])*) $(,)*
     ) => {
-        mk_lense_struct!{ @as_item
-            $(#[$meta])* pub enum $ident<M> where $($($ty: $crate::Mode<M>),*),* {
+        $crate::mk_lense_struct!{ @as_item
+            $(#[$meta])* pub enum $ident<'a, M> where $($($ty: $crate::Mode<'a, M>,)*)* {
                 InvalidLense,
+                // Uses `'a` and `M` when no variant holds a field
+                #[doc(hidden)]
+                __Phantom(::std::convert::Infallible, ::std::marker::PhantomData<(&'a (), M)>),
                 $($builder_struct)*
             }
         }
-        mk_lense_struct!{ E $ident $($field($($ty),*))* }
+        $crate::mk_lense_struct!{ E $ident [$tag] ($($variant)*)
+            $($variant [$($unit)?] ($($field: $ty),*) [$($disc)?] [$($writer)?])*
+        }
     };
-    (@enum private ([$($meta:tt)*] $ident:ident $($builder_struct:tt)*)
-                   ($($field:ident($($ty:ty),*))*) $(,)*
+    (@enum private ([$($meta:tt)*] $ident:ident [$tag:ty] $($builder_struct:tt)*)
+                   ($($variant:ident [$($unit:ident)?] ($($field:ident: $ty:ty),*)
+                      [$($disc:expr)?] [$($writer:ident)?])*) $(,)*
     ) => {
-        mk_lense_struct!{ @as_item
-            enum $ident<M> where $($($ty: $crate::Mode<M>),*),* {
+        $crate::mk_lense_struct!{ @as_item
+            enum $ident<'a, M> where $($($ty: $crate::Mode<'a, M>,)*)* {
                 InvalidLense,
+                // Uses `'a` and `M` when no variant holds a field
+                #[doc(hidden)]
+                __Phantom(::std::convert::Infallible, ::std::marker::PhantomData<(&'a (), M)>),
                 $($builder_struct)*
             }
         }
-        mk_lense_struct!{ E $ident $($field($($ty),*))* }
+        $crate::mk_lense_struct!{ E $ident [$tag] ($($variant)*)
+            $($variant [$($unit)?] ($($field: $ty),*) [$($disc)?] [$($writer)?])*
+        }
     };
 
     (@enum $vis:tt ($($builder_struct:tt)*) ($($builder_impl:tt)*)
         #[$attr:meta] $($tt:tt)*
     ) => {
-        mk_lense_struct!{ @enum $vis
+        $crate::mk_lense_struct!{ @enum $vis
             ($($builder_struct)* #[$attr])
             ($($builder_impl)*)
             $($tt)*
         }
     };
     (@enum $vis:tt ($($builder_struct:tt)*) ($($builder_impl:tt)*)
-        $ident:ident($ty:ty) , $($tt:tt)*
+        $ident:ident($($ty:ty),*) $(= $disc:expr)? $(=> $writer:ident)? , $($tt:tt)*
     ) => {
-        mk_lense_struct!{ @enum $vis
-            ($($builder_struct)* $ident(<$ty as $crate::Mode<M>>::Return),)
-            ($($builder_impl)* $ident($ty))
+        $crate::mk_lense_struct!{ @enum_field $vis
+            ($($builder_struct)* $ident($(<$ty as $crate::Mode<'a, M>>::Return),*),)
+            ($($builder_impl)*) $ident [$($disc)?] [$($writer)?] () ($($ty,)*)
+            $($tt)*
+        }
+    };
+    (@enum $vis:tt ($($builder_struct:tt)*) ($($builder_impl:tt)*)
+        $ident:ident $(= $disc:expr)? $(=> $writer:ident)? , $($tt:tt)*
+    ) => {
+        $crate::mk_lense_struct!{ @enum $vis
+            ($($builder_struct)* $ident,)
+            ($($builder_impl)* $ident [unit] () [$($disc)?] [$($writer)?])
+            $($tt)*
+        }
+    };
+
+    // Name each field of a variant so its lense can be matched. Every step is its own
+    // expansion, so the `field` introduced by each one is a distinct binding.
+    (@enum_field $vis:tt ($($builder_struct:tt)*) ($($builder_impl:tt)*)
+        $ident:ident [$($disc:expr)?] [$($writer:ident)?]
+        ($($field:ident: $fty:ty,)*) ($ty:ty, $($rest:ty,)*)
+        $($tt:tt)*
+    ) => {
+        $crate::mk_lense_struct!{ @enum_field $vis ($($builder_struct)*) ($($builder_impl)*)
+            $ident [$($disc)?] [$($writer)?] ($($field: $fty,)* field: $ty,) ($($rest,)*)
+            $($tt)*
+        }
+    };
+    (@enum_field $vis:tt ($($builder_struct:tt)*) ($($builder_impl:tt)*)
+        $ident:ident [$($disc:expr)?] [$($writer:ident)?] ($($field:ident: $ty:ty,)*) ()
+        $($tt:tt)*
+    ) => {
+        $crate::mk_lense_struct!{ @enum $vis
+            ($($builder_struct)*)
+            ($($builder_impl)* $ident [] ($($field: $ty),*) [$($disc)?] [$($writer)?])
             $($tt)*
         }
     };
@@ -228,30 +418,70 @@ macro_rules! mk_lense_struct {
     // Lense struct implementations
 
     ({} $ident:ident $($field:ident: $ty:ty),* $(,)*) => {
-        impl<M> $crate::Lense for $ident<M>
-            where $($ty: $crate::Mode<M>),*
+        impl<'x, M> $crate::Lense for $ident<'x, M>
+            where $($ty: $crate::Mode<'x, M>),*
         {
-            type Ref = $ident<$crate::IsRef>;
+            type Ref<'a> = $ident<'a, $crate::IsRef>;
+
+            const SIZE: usize = $crate::composite_size(&[$(
+                (<$ty as $crate::Lense>::SIZE, <$ty as $crate::Lense>::ALIGN)
+            ),*]);
+            const ALIGN: usize = $crate::max_of(&[1 $(, <$ty as $crate::Lense>::ALIGN)*]);
+            const FIXED_SIZE: bool = true $(&& <$ty as $crate::Lense>::FIXED_SIZE)*;
 
             #[inline]
-            fn size() -> usize {
-                0usize $(+ <$ty>::size())*
+            #[allow(unused_variables)]
+            fn try_lense<'a, Buf: $crate::DiceRef<'a>>(buf: &mut Buf)
+                -> Result<Self::Ref<'a>, $crate::LenseError>
+            {
+                buf.pad_to(<Self as $crate::Lense>::ALIGN)?;
+                let lense = $ident::<$crate::IsRef> {
+                    $($field: <$ty as $crate::Lense>::try_lense(buf)?),*
+                };
+                buf.pad_to(<Self as $crate::Lense>::ALIGN)?;
+                Ok(lense)
             }
+        }
+
+        impl<'x> $crate::LenseMut for $ident<'x, $crate::IsMut> {
+            type Mut<'a> = $ident<'a, $crate::IsMut>;
 
             #[inline]
             #[allow(unused_variables)]
-            fn lense<Buf: $crate::DiceRef>(buf: &mut Buf) -> Self::Ref {
-                $ident::<$crate::IsRef> { $($field: <$ty>::lense(buf)),* }
+            fn try_lense_mut<'a, Buf: $crate::DiceMut<'a>>(buf: &mut Buf)
+                -> Result<Self::Mut<'a>, $crate::LenseError>
+            {
+                buf.pad_to(<Self as $crate::Lense>::ALIGN)?;
+                let lense = $ident { $($field: <$ty as $crate::LenseMut>::try_lense_mut(buf)?),* };
+                buf.pad_to(<Self as $crate::Lense>::ALIGN)?;
+                Ok(lense)
             }
         }
 
-        impl $crate::LenseMut for $ident<$crate::IsMut> {
-            type Mut = $ident<$crate::IsMut>;
+        impl<'x> $crate::Reborrow for $ident<'x, $crate::IsRef> {
+            type Ref<'g> = $ident<'g, $crate::IsRef> where Self: 'g;
 
             #[inline]
-            #[allow(unused_variables)]
-            fn lense_mut<Buf: $crate::DiceMut>(buf: &mut Buf) -> Self::Mut {
-                $ident { $($field: <$ty>::lense_mut(buf)),* }
+            fn reborrow(&self) -> Self::Ref<'_> {
+                $ident { $($field: $crate::Reborrow::reborrow(&self.$field)),* }
+            }
+        }
+
+        impl<'x> $crate::Reborrow for $ident<'x, $crate::IsMut> {
+            type Ref<'g> = $ident<'g, $crate::IsRef> where Self: 'g;
+
+            #[inline]
+            fn reborrow(&self) -> Self::Ref<'_> {
+                $ident { $($field: $crate::Reborrow::reborrow(&self.$field)),* }
+            }
+        }
+
+        impl<'x> $crate::ReborrowMut for $ident<'x, $crate::IsMut> {
+            type Mut<'g> = $ident<'g, $crate::IsMut> where Self: 'g;
+
+            #[inline]
+            fn reborrow_mut(&mut self) -> Self::Mut<'_> {
+                $ident { $($field: $crate::ReborrowMut::reborrow_mut(&mut self.$field)),* }
             }
         }
     };
@@ -259,50 +489,208 @@ macro_rules! mk_lense_struct {
     // Enum variant counter
 
     (@void $void:tt $expr:expr) => { $expr };
-    (@count_cont $($elem:tt)*) => { 0u8 $(+ mk_lense_struct!{@void $elem 1u8})* };
+    (@count_cont $($elem:tt)*) => { 0usize $(+ $crate::mk_lense_struct!{@void $elem 1usize})* };
     (@count ($($tt:expr),*) $void:tt $($tail:tt)*) => {
-        mk_lense_struct!{@count (mk_lense_struct!{@count_cont $($tail)*} $(, $tt)*) $($tail)*}
+        $crate::mk_lense_struct!{@count ($crate::mk_lense_struct!{@count_cont $($tail)*} $(, $tt)*) $($tail)*}
     };
-    (@count $expr:expr) => { $expr };
+    (@count ($($tt:expr),*)) => { ($($tt,)*) };
+
+    // Position of a variant among all of the enum's variants
+    (@index $variant:ident ($($all:ident)*)) => {{
+        #[allow(non_snake_case, unused_variables)]
+        let ($($all,)*) = $crate::mk_lense_struct!(@count () $($all)*);
+        $variant
+    }};
+
+    // Build, or match, a variant from its fields. Unit variants are marked `[unit]`.
+    (@make $ident:ident $variant:ident [unit] ()) => { $ident::$variant };
+    (@make $ident:ident $variant:ident [] ($($field:expr),*)) => { $ident::$variant($($field),*) };
+    (@pat $ident:ident $variant:ident [unit] ()) => { $ident::$variant };
+    (@pat $ident:ident $variant:ident [] ($($field:ident),*)) => { $ident::$variant($($field),*) };
+
+    // Enum discriminants
+
+    (@disc) => { None };
+    (@disc $disc:expr) => { Some($disc) };
 
     // Lense enum implementations
 
-    (E $ident:ident $($variant:ident($($ty:ty),*))*) => {
-        impl<M> $crate::Lense for $ident<M>
-            where $($($ty: $crate::Mode<M>),*),*
+    (E $ident:ident [$tag:ty] $all:tt
+        $($variant:ident [$($unit:ident)?] ($($field:ident: $ty:ty),*) [$($disc:expr)?]
+          [$($writer:ident)?])*
+    ) => {
+        impl<'x, M> $ident<'x, M> where $($($ty: $crate::Mode<'x, M>,)*)* {
+            // Bytes reserved for the payload: the largest variant
+            const PAYLOAD: usize = $crate::max_of(&[$(
+                $crate::composite_size(&[$((<$ty as $crate::Lense>::SIZE,
+                                            <$ty as $crate::Lense>::ALIGN)),*])
+            ),*]);
+
+            // Tag of each variant, in declaration order
+            const DISCRIMINANTS: &'static [u64] = &$crate::discriminants(
+                [$($crate::mk_lense_struct!(@disc $($disc)?)),*],
+                <$tag as $crate::Tag>::MAX);
+        }
+
+        impl<'x> $ident<'x, $crate::IsRef> {
+            #[inline]
+            #[allow(non_snake_case, unused_variables)]
+            fn lense_variant(tag: u64, payload: &'x [u8]) -> Result<Self, $crate::LenseError> {
+                $($($crate::assert_fixed_size::<$ty>();)*)*
+                let payload = &mut $crate::Aligned::new(payload);
+                let ($($variant,)*) = $crate::mk_lense_struct!(@count () $( $variant )*);
+                Ok(match tag {
+                    $(x if x == Self::DISCRIMINANTS[$variant] => $crate::mk_lense_struct!(
+                        @make $ident $variant [$($unit)?]
+                        ($(<$ty as $crate::Lense>::try_lense(payload)?),*)), )*
+                    _ => return Err($crate::LenseError::UnknownTag { tag }),
+                })
+            }
+        }
+
+        impl<'x> $ident<'x, $crate::IsMut> {
+            #[inline]
+            #[allow(non_snake_case, unused_variables)]
+            fn lense_variant_mut(tag: u64, payload: &'x mut [u8])
+                -> Result<Self, $crate::LenseError>
+            {
+                $($($crate::assert_fixed_size::<$ty>();)*)*
+                let payload = &mut $crate::Aligned::new(payload);
+                let ($($variant,)*) = $crate::mk_lense_struct!(@count () $( $variant )*);
+                Ok(match tag {
+                    $(x if x == Self::DISCRIMINANTS[$variant] => $crate::mk_lense_struct!(
+                        @make $ident $variant [$($unit)?]
+                        ($(<$ty as $crate::LenseMut>::try_lense_mut(payload)?),*)), )*
+                    _ => return Err($crate::LenseError::UnknownTag { tag }),
+                })
+            }
+
+            $($(
+                #[doc = concat!("Write the `", stringify!($variant), "` tag and a zeroed payload ",
+                                "to the guarded record, lensing it mutably as the new variant. ",
+                                "Panics, leaving the record as it was, when the zeroed payload ",
+                                "doesn't lense.")]
+                #[allow(dead_code)]
+                pub fn $writer<'g, L>(guard: &'g mut $crate::Guard<'x, Self, L>)
+                    -> $ident<'g, $crate::IsMut>
+                    where L: for<'a> $crate::LenseMut<Mut<'a> = $ident<'a, $crate::IsMut>>
+                {
+                    let tag = Self::DISCRIMINANTS[$crate::mk_lense_struct!(@index $variant $all)];
+                    guard.rewrite(|bytes| {
+                        for byte in bytes.iter_mut() {
+                            *byte = 0;
+                        }
+                        *<$tag as $crate::LenseMut>::lense_mut(&mut &mut *bytes) =
+                            <$tag as $crate::Tag>::from_tag(tag);
+                    }).unwrap_or_else(|e| panic!("{}", e));
+                    guard.lense_mut()
+                }
+            )?)*
+        }
+
+        impl<'x, M> $crate::Lense for $ident<'x, M>
+            where $($($ty: $crate::Mode<'x, M>,)*)*
         {
-            type Ref = $ident<$crate::IsRef>;
+            type Ref<'a> = $ident<'a, $crate::IsRef>;
+
+            const SIZE: usize = $crate::composite_size(&[
+                (<$tag as $crate::Lense>::SIZE, <$tag as $crate::Lense>::ALIGN),
+                (Self::PAYLOAD, Self::ALIGN),
+            ]);
+            const ALIGN: usize = $crate::max_of(&[<$tag as $crate::Lense>::ALIGN
+                                                  $($(, <$ty as $crate::Lense>::ALIGN)*)*]);
+
+            #[inline]
+            fn try_lense<'a, Buf: $crate::DiceRef<'a>>(buf: &mut Buf)
+                -> Result<Self::Ref<'a>, $crate::LenseError>
+            {
+                buf.pad_to(<Self as $crate::Lense>::ALIGN)?;
+                let tag = $crate::Tag::to_tag(buf.try_dice::<$tag>()?);
+                buf.pad_to(<Self as $crate::Lense>::ALIGN)?;
+                let payload = buf.try_dice_bytes(Self::PAYLOAD)?;
+                buf.pad_to(<Self as $crate::Lense>::ALIGN)?;
+                $ident::<$crate::IsRef>::lense_variant(tag, payload)
+            }
 
             #[inline]
-            fn size() -> usize {
-                *[$( <($($ty),*) as $crate::Lense>::size() ),*].iter().max().unwrap()
+            fn try_lense_record<'a, Buf: $crate::DiceRef<'a>>(buf: &mut Buf)
+                -> Result<Self::Ref<'a>, $crate::LenseError>
+            {
+                match <Self as $crate::Lense>::try_lense(buf) {
+                    Err($crate::LenseError::UnknownTag { .. }) =>
+                        Ok($ident::<$crate::IsRef>::InvalidLense),
+                    lense => lense,
+                }
             }
+        }
+
+        impl<'x> $crate::LenseMut for $ident<'x, $crate::IsMut> {
+            type Mut<'a> = $ident<'a, $crate::IsMut>;
+
+            #[inline]
+            fn try_lense_mut<'a, Buf: $crate::DiceMut<'a>>(buf: &mut Buf)
+                -> Result<Self::Mut<'a>, $crate::LenseError>
+            {
+                buf.pad_to(<Self as $crate::Lense>::ALIGN)?;
+                let tag = $crate::Tag::to_tag(buf.try_dice::<$tag>()?);
+                buf.pad_to(<Self as $crate::Lense>::ALIGN)?;
+                let payload = buf.try_dice_bytes_mut(Self::PAYLOAD)?;
+                buf.pad_to(<Self as $crate::Lense>::ALIGN)?;
+                $ident::lense_variant_mut(tag, payload)
+            }
+
+            #[inline]
+            fn try_lense_record_mut<'a, Buf: $crate::DiceMut<'a>>(buf: &mut Buf)
+                -> Result<Self::Mut<'a>, $crate::LenseError>
+            {
+                match <Self as $crate::LenseMut>::try_lense_mut(buf) {
+                    Err($crate::LenseError::UnknownTag { .. }) => Ok($ident::InvalidLense),
+                    lense => lense,
+                }
+            }
+        }
+
+        impl<'x> $crate::Reborrow for $ident<'x, $crate::IsRef> {
+            type Ref<'g> = $ident<'g, $crate::IsRef> where Self: 'g;
+
+            #[inline]
+            fn reborrow(&self) -> Self::Ref<'_> {
+                match self {
+                    $ident::InvalidLense => $ident::InvalidLense,
+                    $ident::__Phantom(never, _) => match *never {},
+                    $($crate::mk_lense_struct!(@pat $ident $variant [$($unit)?] ($($field),*)) =>
+                        $crate::mk_lense_struct!(@make $ident $variant [$($unit)?]
+                            ($($crate::Reborrow::reborrow($field)),*)),)*
+                }
+            }
+        }
+
+        impl<'x> $crate::Reborrow for $ident<'x, $crate::IsMut> {
+            type Ref<'g> = $ident<'g, $crate::IsRef> where Self: 'g;
 
             #[inline]
-            #[allow(non_snake_case)]
-            fn lense<Buf: $crate::DiceRef>(buf: &mut Buf) -> Self::Ref {
-                let tag = <u8>::lense(buf);
-                let ($($variant,)*) = mk_lense_struct!(@count () $( $variant )*);
-                match tag {
-                    $(x if *x == $variant =>
-                        $ident::$variant::<$crate::IsRef>(<($($ty),*)>::lense(buf)), )*
-                    _ => $ident::InvalidLense::<$crate::IsRef>,
+            fn reborrow(&self) -> Self::Ref<'_> {
+                match self {
+                    $ident::InvalidLense => $ident::InvalidLense,
+                    $ident::__Phantom(never, _) => match *never {},
+                    $($crate::mk_lense_struct!(@pat $ident $variant [$($unit)?] ($($field),*)) =>
+                        $crate::mk_lense_struct!(@make $ident $variant [$($unit)?]
+                            ($($crate::Reborrow::reborrow($field)),*)),)*
                 }
             }
         }
 
-        impl $crate::LenseMut for $ident<$crate::IsMut> {
-            type Mut = $ident<$crate::IsMut>;
+        impl<'x> $crate::ReborrowMut for $ident<'x, $crate::IsMut> {
+            type Mut<'g> = $ident<'g, $crate::IsMut> where Self: 'g;
 
             #[inline]
-            #[allow(non_snake_case)]
-            fn lense_mut<Buf: $crate::DiceMut>(buf: &mut Buf) -> Self::Mut {
-                let tag = <u8>::lense(buf);
-                let ($($variant,)*) = mk_lense_struct!(@count () $( $variant )*);
-                match tag {
-                    $(x if *x == $variant =>
-                        $ident::$variant(<($($ty),*)>::lense_mut(buf)), )*
-                    _ => $ident::InvalidLense,
+            fn reborrow_mut(&mut self) -> Self::Mut<'_> {
+                match self {
+                    $ident::InvalidLense => $ident::InvalidLense,
+                    $ident::__Phantom(never, _) => match *never {},
+                    $($crate::mk_lense_struct!(@pat $ident $variant [$($unit)?] ($($field),*)) =>
+                        $crate::mk_lense_struct!(@make $ident $variant [$($unit)?]
+                            ($($crate::ReborrowMut::reborrow_mut($field)),*)),)*
                 }
             }
         }
@@ -310,5 +698,5 @@ macro_rules! mk_lense_struct {
 
     // Start parsing
 
-    ($($tt:tt)*) => { mk_lense_struct!{ [] $($tt)* } };
+    ($($tt:tt)*) => { $crate::mk_lense_struct!{ [] $($tt)* } };
 }
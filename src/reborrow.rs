@@ -0,0 +1,110 @@
+/// A lense, or a projection of one, that can be borrowed again for a shorter lifetime
+///
+/// Guards keep the lense they lock to themselves and only lend it out through `Reborrow`, so
+/// nothing taken from a guard can outlive its lock.
+pub trait Reborrow {
+    /// The lense borrowed immutably for `'g`
+    type Ref<'g> where Self: 'g;
+
+    fn reborrow(&self) -> Self::Ref<'_>;
+}
+
+/// A mutable lense that can be borrowed again mutably for a shorter lifetime
+pub trait ReborrowMut: Reborrow {
+    /// The lense borrowed mutably for `'g`
+    type Mut<'g> where Self: 'g;
+
+    fn reborrow_mut(&mut self) -> Self::Mut<'_>;
+}
+
+impl<T: ?Sized> Reborrow for &T {
+    type Ref<'g> = &'g T where Self: 'g;
+
+    #[inline]
+    fn reborrow(&self) -> Self::Ref<'_> {
+        self
+    }
+}
+
+impl<T: ?Sized> Reborrow for &mut T {
+    type Ref<'g> = &'g T where Self: 'g;
+
+    #[inline]
+    fn reborrow(&self) -> Self::Ref<'_> {
+        self
+    }
+}
+
+impl<T: ?Sized> ReborrowMut for &mut T {
+    type Mut<'g> = &'g mut T where Self: 'g;
+
+    #[inline]
+    fn reborrow_mut(&mut self) -> Self::Mut<'_> {
+        self
+    }
+}
+
+impl<T: Reborrow, const N: usize> Reborrow for [T; N] {
+    type Ref<'g> = [T::Ref<'g>; N] where Self: 'g;
+
+    #[inline]
+    fn reborrow(&self) -> Self::Ref<'_> {
+        self.each_ref().map(Reborrow::reborrow)
+    }
+}
+
+impl<T: ReborrowMut, const N: usize> ReborrowMut for [T; N] {
+    type Mut<'g> = [T::Mut<'g>; N] where Self: 'g;
+
+    #[inline]
+    fn reborrow_mut(&mut self) -> Self::Mut<'_> {
+        self.each_mut().map(ReborrowMut::reborrow_mut)
+    }
+}
+
+macro_rules! mk_reborrow {
+    (()) => {
+        impl Reborrow for () {
+            type Ref<'g> = ();
+
+            #[inline]
+            fn reborrow(&self) {}
+        }
+
+        impl ReborrowMut for () {
+            type Mut<'g> = ();
+
+            #[inline]
+            fn reborrow_mut(&mut self) {}
+        }
+    };
+    (() $head:ident $($tail:ident)*) => {
+        impl<$head: Reborrow $(, $tail: Reborrow)*> Reborrow for ($head, $($tail,)*) {
+            type Ref<'g> = ($head::Ref<'g>, $($tail::Ref<'g>,)*) where Self: 'g;
+
+            #[inline]
+            #[allow(non_snake_case)]
+            fn reborrow(&self) -> Self::Ref<'_> {
+                let ($head, $($tail,)*) = self;
+                ($head.reborrow(), $($tail.reborrow(),)*)
+            }
+        }
+
+        impl<$head: ReborrowMut $(, $tail: ReborrowMut)*> ReborrowMut for ($head, $($tail,)*) {
+            type Mut<'g> = ($head::Mut<'g>, $($tail::Mut<'g>,)*) where Self: 'g;
+
+            #[inline]
+            #[allow(non_snake_case)]
+            fn reborrow_mut(&mut self) -> Self::Mut<'_> {
+                let ($head, $($tail,)*) = self;
+                ($head.reborrow_mut(), $($tail.reborrow_mut(),)*)
+            }
+        }
+        mk_reborrow!{ () $($tail)* }
+    };
+}
+
+mk_reborrow!{ ()
+    A B C D E F
+    G H I J K L
+}
@@ -1,191 +1,819 @@
 use std::cell::Cell;
-
-use {Lense, LenseMut, Mode, IsRef, IsMut};
-use aligned::Aligned;
-
-/// A 8-byte aligned random access backing collection supporting locking on borrows to prevent
-/// aliasing.
-pub struct SeekablePool<L: Lense> {
-    // Backing u64 pool
-    pool: Vec<u64>,
-    // Lock state
-    state: Vec<Cell<bool>>,
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use {Lense, LenseError, LenseMut, LockingIter, Mode, IsRef, IsMut, PoolError, Reborrow,
+     ReborrowMut};
+use aligned::{assert_fixed_size, Aligned};
+use chunks::{Chunk, ChunkMut};
+
+/// A random access backing collection supporting locking on borrows to prevent aliasing.
+///
+/// The storage is aligned to the backing word `A`. The default `u64` packs records back to back
+/// on an 8-byte aligned base; the over-aligned words start every record on their own boundary.
+///
+/// Guards and the lenses they hold borrow the pool and cannot outlive it.
+///
+/// ```compile_fail
+/// use lense::SeekablePool;
+///
+/// let stale: &u16 = {
+///     let pool = SeekablePool::<u16>::with_capacity(1);
+///     let guard = pool.iter().next().unwrap();
+///     guard.lense()
+/// };
+/// ```
+pub struct SeekablePool<L: Lense, A: PoolWord = u64> {
+    // Backing words, written through shared guards
+    pool: Vec<Cell<A>>,
+    // Lock state: the number of shared guards, or WRITER plus the number of pieces a mutable
+    // guard has been split into
+    state: Vec<Cell<usize>>,
     // The pool is optimized for this type
     marker: ::std::marker::PhantomData<*const L>,
 }
 
+/// The word backing a `SeekablePool`. The pool's storage takes the word's alignment, and each
+/// record is padded to a multiple of `STRIDE` bytes.
+///
+/// # Safety
+///
+/// Implementors must be plain bytes whose `ZERO` is all zeroes.
+pub unsafe trait PoolWord: Copy + 'static {
+    /// Records start on multiples of this many bytes
+    const STRIDE: usize;
+    /// The zeroed word
+    const ZERO: Self;
+}
+
+unsafe impl PoolWord for u64 {
+    const STRIDE: usize = 1;
+    const ZERO: Self = 0;
+}
+
+macro_rules! mk_pool_word {
+    ($($word:ident: $align:tt,)*) => {$(
+        #[doc = concat!("Pool storage aligned to ", $align, " bytes, starting every record on a ",
+                        $align, "-byte boundary")]
+        #[derive(Clone, Copy)]
+        #[repr(C, align($align))]
+        pub struct $word([u8; $align]);
+
+        unsafe impl PoolWord for $word {
+            const STRIDE: usize = $align;
+            const ZERO: Self = $word([0; $align]);
+        }
+    )*};
+}
+
+mk_pool_word!{
+    Align16: 16,
+    Align32: 32,
+    Align64: 64,
+    Align4096: 4096,
+}
+
+const WRITER: usize = 1 << (usize::BITS - 1);
+
 fn div_up(n: usize, m: usize) -> usize {
-    if n % m == 0 {
-        n / m
-    } else {
-        n / m + 1
-    }
+    n.div_ceil(m)
+}
+
+// Bytes from the start of one record to the next
+pub fn stride<L: Lense, A: PoolWord>() -> usize {
+    assert_fixed_size::<L>();
+    div_up(L::SIZE, A::STRIDE) * A::STRIDE
+}
+
+pub fn check_range(range: &Range<usize>, len: usize) {
+    assert!(range.start <= range.end && range.end <= len,
+            "Invalid range! {}..{} for a pool of {}", range.start, range.end, len);
 }
 
 impl<L: Lense> SeekablePool<L> {
-    /// Prepare a collection to store `cap` of type L
+    /// An empty collection, grown with `push_zeroed`, `push_with` or `resize`
+    pub fn new() -> Self {
+        SeekablePool::with_capacity(0)
+    }
+
+    /// Prepare a collection of `cap` zeroed records of type L
     pub fn with_capacity(cap: usize) -> Self {
+        SeekablePool::aligned(cap)
+    }
+}
+
+impl<L: Lense, A: PoolWord> SeekablePool<L, A> {
+    /// Prepare a collection of `cap` zeroed records of type L, each starting on a boundary of
+    /// the backing word `A`
+    ///
+    /// ```
+    /// use lense::{Align64, SeekablePool};
+    ///
+    /// let pool = SeekablePool::<u32, Align64>::aligned(2);
+    /// assert_eq!(pool.as_bytes().lense().len(), 128);
+    /// assert_eq!(pool.get(1).lense() as *const u32 as usize % 64, 0);
+    /// ```
+    pub fn aligned(cap: usize) -> Self {
+        let word = ::std::mem::size_of::<A>();
         SeekablePool {
-            pool: vec![0u64; div_up(cap * L::size(), 8)],
-            state: vec![Cell::new(false); cap],
+            pool: vec![Cell::new(A::ZERO); div_up(cap * stride::<L, A>(), word)],
+            state: vec![Cell::new(0); cap],
             marker: ::std::marker::PhantomData,
         }
     }
 
-    fn lense(&self, pos: usize) -> Option<Guard<<L as Mode<IsRef>>::Return>> {
-        match self.state.get(pos) {
-            Some(ref mut lock) if !lock.get() => {
-                let ref mut ptr = Aligned::new(unsafe { // &self[L::size() * pos .. L::size()]
-                    ::std::slice::from_raw_parts(
-                        (self.pool.as_ptr() as *const u8).offset((L::size() * pos) as isize),
-                        L::size())
-                });
+    /// Number of live records
+    pub fn len(&self) -> usize {
+        self.state.len()
+    }
+
+    /// True when the collection holds no records
+    pub fn is_empty(&self) -> bool {
+        self.state.is_empty()
+    }
+
+    /// Number of records the collection can hold without reallocating
+    pub fn capacity(&self) -> usize {
+        let word = ::std::mem::size_of::<A>();
+        match stride::<L, A>() {
+            0 => self.state.capacity(),
+            size => ::std::cmp::min(self.state.capacity(), self.pool.capacity() * word / size),
+        }
+    }
+
+    /// The bytes of the live records, locked like an immutable guard over every record. Panics
+    /// while any record is lensed mutably.
+    pub fn as_bytes(&self) -> Guard<'_, &[u8]> {
+        self.records().try_get_bytes()
+            .unwrap_or_else(|_| panic!("Pool has outstanding mutable guards"))
+    }
+
+    /// The bytes of the live records
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        unsafe { // Vec<A> -> &mut [u8]
+            ::std::slice::from_raw_parts_mut(self.pool.as_mut_ptr() as *mut u8,
+                                             self.len() * stride::<L, A>())
+        }
+    }
 
-                lock.set(true);
+    /// Reserve storage for at least `additional` more records
+    pub fn reserve(&mut self, additional: usize) {
+        let words = div_up((self.state.len() + additional) * stride::<L, A>(),
+                           ::std::mem::size_of::<A>());
+        self.pool.reserve(words - self.pool.len());
+        self.state.reserve(additional);
+    }
 
-                Some(Guard { lock: &lock, value: L::lense(ptr) })
+    /// Grow or shrink the collection to `len` records; new records are zeroed.
+    ///
+    /// Reallocating takes `&mut self`, so no `Guard` can be outstanding while the storage moves.
+    pub fn resize(&mut self, len: usize) {
+        let old = self.state.len();
+        let stride = stride::<L, A>();
+        self.pool.resize(div_up(len * stride, ::std::mem::size_of::<A>()), Cell::new(A::ZERO));
+        self.state.resize(len, Cell::new(0));
+        if len > old {
+            // A previous truncate may have left bytes behind in the last word
+            for byte in &mut self.as_bytes_mut()[old * stride..] {
+                *byte = 0;
             }
-            Some(..) => None,
-            None => panic!("Invalid index! {}", pos),
         }
     }
 
-    fn lense_mut(&self, pos: usize) -> Option<Guard<<L as Mode<IsMut>>::Return>> where L: LenseMut {
-        match self.state.get(pos) {
-            Some(ref mut lock) if !lock.get() => {
-                let ref mut ptr = Aligned::new(unsafe { // &mut self[L::size() * pos .. L::size()]
-                    ::std::slice::from_raw_parts_mut(
-                        (self.pool.as_ptr() as *mut u8).offset((L::size() * pos) as isize),
-                        L::size())
-                });
+    /// Drop every record from `len` onwards
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.state.len() {
+            self.resize(len);
+        }
+    }
+
+    /// Append a zeroed record and return its position
+    pub fn push_zeroed(&mut self) -> usize {
+        let pos = self.state.len();
+        self.resize(pos + 1);
+        pos
+    }
 
-                lock.set(true);
+    /// Append a zeroed record, initialise it through its mutable lense and return its position
+    pub fn push_with<F>(&mut self, f: F) -> usize
+        where L: LenseMut, F: for<'a> FnOnce(<L as Mode<'a, IsMut>>::Return)
+    {
+        let pos = self.push_zeroed();
+        let stride = stride::<L, A>();
+        f(L::lense_mut(&mut Aligned::new(&mut self.as_bytes_mut()[pos * stride..])));
+        pos
+    }
 
-                Some(Guard { lock: &lock, value: L::lense_mut(ptr) })
+    /// Remove the last record, returning false when the collection is empty
+    pub fn pop(&mut self) -> bool {
+        match self.state.len() {
+            0 => false,
+            len => {
+                self.truncate(len - 1);
+                true
             }
-            Some(..) => None,
-            None => panic!("Invalid index! {}", pos),
         }
     }
 
-    /// Iterate immutably over the pool's collection of lenses.
-    pub fn iter(&self) -> IterRef<L> {
-        IterRef { pool: self, cur: 0 }
+    /// Lense the record at `pos`, failing when it is out of bounds or lensed mutably.
+    pub fn try_get<'a>(&'a self, pos: usize)
+        -> Result<Guard<'a, <L as Mode<'a, IsRef>>::Return, L>, PoolError>
+    {
+        self.records().try_get(pos)
+    }
+
+    /// Lense the record at `pos` mutably, failing when it is out of bounds or already locked.
+    pub fn try_get_mut<'a>(&'a self, pos: usize)
+        -> Result<Guard<'a, <L as Mode<'a, IsMut>>::Return, L>, PoolError>
+        where L: LenseMut
+    {
+        self.records().try_get_mut(pos)
+    }
+
+    /// Lense the record at `pos`. Panics when it is out of bounds or lensed mutably.
+    pub fn get<'a>(&'a self, pos: usize) -> Guard<'a, <L as Mode<'a, IsRef>>::Return, L> {
+        self.try_get(pos).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Lense the record at `pos` mutably. Panics when it is out of bounds or already locked.
+    pub fn get_mut<'a>(&'a self, pos: usize) -> Guard<'a, <L as Mode<'a, IsMut>>::Return, L>
+        where L: LenseMut
+    {
+        self.try_get_mut(pos).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Lense every record in `range`. Either all of them are locked or, on error, none are.
+    pub fn try_get_range<'a>(&'a self, range: Range<usize>)
+        -> Result<Vec<RefGuard<'a, L>>, PoolError>
+    {
+        range.map(|pos| self.try_get(pos)).collect()
+    }
+
+    /// Lense every record in `range` mutably. Either all of them are locked or, on error, none
+    /// are.
+    pub fn try_get_range_mut<'a>(&'a self, range: Range<usize>)
+        -> Result<Vec<MutGuard<'a, L>>, PoolError>
+        where L: LenseMut
+    {
+        range.map(|pos| self.try_get_mut(pos)).collect()
+    }
+
+    /// Lock the records in `range` as a group under a single guard.
+    pub fn try_get_chunk<'a>(&'a self, range: Range<usize>)
+        -> Result<Guard<'a, Chunk<'a, L>>, PoolError>
+    {
+        self.records().try_get_chunk(range)
+    }
+
+    /// Lock the records in `range` mutably as a group under a single guard.
+    pub fn try_get_chunk_mut<'a>(&'a self, range: Range<usize>)
+        -> Result<Guard<'a, ChunkMut<'a, L>>, PoolError>
+        where L: LenseMut
+    {
+        self.records().try_get_chunk_mut(range)
+    }
+
+    // The live records, writable through the Cell storage while their locks are held
+    pub(crate) fn records(&self) -> Records<'_, L> {
+        Records::new(self.pool.as_ptr() as *mut u8, &self.state, stride::<L, A>())
+    }
+
+    /// Iterate immutably over the pool's collection of lenses, ending at the first slot that is
+    /// lensed mutably. See `LockingIter` for other ways to handle locked slots.
+    pub fn iter(&self) -> IterRef<'_, L> {
+        self.records().iter(0..self.len())
     }
 
     /// Iterate mutably over the pool's collection of lenses.
-    pub fn iter_mut(&mut self) -> IterMut<L> where L: LenseMut {
-        IterMut { pool: self, cur: 0 }
+    pub fn iter_mut(&mut self) -> IterMut<'_, L> where L: LenseMut {
+        self.records().iter_mut(0..self.len())
+    }
+
+    /// Iterate immutably over the lenses in `range`. Panics when the range is out of bounds.
+    pub fn range(&self, range: Range<usize>) -> IterRef<'_, L> {
+        check_range(&range, self.len());
+        self.records().iter(range)
+    }
+
+    /// Iterate mutably over the lenses in `range`. Panics when the range is out of bounds.
+    pub fn range_mut(&mut self, range: Range<usize>) -> IterMut<'_, L> where L: LenseMut {
+        check_range(&range, self.len());
+        self.records().iter_mut(range)
     }
 }
 
-impl<L: Lense> ::std::ops::Deref for SeekablePool<L> {
-    type Target = [u8];
+/// Records of type L laid out every `stride` bytes from `base`, each locked through its entry in
+/// `locks`. Pools and borrowed slices lense, lock and iterate through this view.
+pub(crate) struct Records<'a, L> {
+    base: *mut u8,
+    locks: &'a [Cell<usize>],
+    stride: usize,
+    marker: PhantomData<fn() -> L>,
+}
 
-    fn deref(&self) -> &Self::Target {
-        unsafe { // Vec<u64> -> &[u8]
-            ::std::slice::from_raw_parts(self.pool.as_ptr() as *const u8,
-                                         self.state.capacity() * L::size())
-        }
+impl<'a, L> Clone for Records<'a, L> {
+    fn clone(&self) -> Self {
+        *self
     }
 }
 
-impl<L: Lense> ::std::ops::DerefMut for SeekablePool<L> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        unsafe { // Vec<u64> -> &mut [u8]
-            ::std::slice::from_raw_parts_mut(self.pool.as_mut_ptr() as *mut u8,
-                                             self.state.capacity() * L::size())
+impl<'a, L> Copy for Records<'a, L> {}
+
+impl<'a, L: Lense> Records<'a, L> {
+    // `base` must stay writable for `locks.len()` records while any lock is held
+    pub(crate) fn new(base: *mut u8, locks: &'a [Cell<usize>], stride: usize) -> Self {
+        Records { base, locks, stride, marker: PhantomData }
+    }
+
+    pub(crate) fn len(self) -> usize {
+        self.locks.len()
+    }
+
+    pub(crate) fn try_get(self, pos: usize)
+        -> Result<Guard<'a, <L as Mode<'a, IsRef>>::Return, L>, PoolError>
+    {
+        let locks = self.lock_shared(pos..pos + 1)?;
+        let record = self.record(pos);
+        let ptr = &mut Aligned::new(unsafe { // &self[stride * pos .. L::SIZE]
+            ::std::slice::from_raw_parts(record, L::SIZE)
+        });
+        let value = L::try_lense_record(ptr).inspect_err(|_| locks.iter().for_each(release))?;
+
+        Ok(Guard { locks, value, record, marker: PhantomData })
+    }
+
+    pub(crate) fn try_get_mut(self, pos: usize)
+        -> Result<Guard<'a, <L as Mode<'a, IsMut>>::Return, L>, PoolError>
+        where L: LenseMut
+    {
+        let locks = self.lock_exclusive(pos..pos + 1)?;
+        let record = self.record(pos);
+        let ptr = &mut Aligned::new(unsafe { // &mut self[stride * pos .. L::SIZE]
+            ::std::slice::from_raw_parts_mut(record, L::SIZE)
+        });
+        let value = L::try_lense_record_mut(ptr).inspect_err(|_| locks.iter().for_each(release))?;
+
+        Ok(Guard { locks, value, record, marker: PhantomData })
+    }
+
+    pub(crate) fn try_get_chunk(self, range: Range<usize>)
+        -> Result<Guard<'a, Chunk<'a, L>>, PoolError>
+    {
+        let locks = self.lock_shared(range.clone())?;
+        let bytes = unsafe { // &self[stride * range.start .. stride * range.end]
+            ::std::slice::from_raw_parts(self.record(range.start), self.stride * locks.len())
+        };
+
+        Ok(Guard::new(locks, Chunk::new(bytes, locks.len(), self.stride)))
+    }
+
+    pub(crate) fn try_get_chunk_mut(self, range: Range<usize>)
+        -> Result<Guard<'a, ChunkMut<'a, L>>, PoolError>
+        where L: LenseMut
+    {
+        let locks = self.lock_exclusive(range.clone())?;
+        let bytes = unsafe { // &mut self[stride * range.start .. stride * range.end]
+            ::std::slice::from_raw_parts_mut(self.record(range.start), self.stride * locks.len())
+        };
+
+        Ok(Guard::new(locks, ChunkMut::new(bytes, locks.len(), self.stride)))
+    }
+
+    pub(crate) fn try_get_bytes(self) -> Result<Guard<'a, &'a [u8]>, PoolError> {
+        let locks = self.lock_shared(0..self.len())?;
+        let bytes = unsafe { // &self[..]
+            ::std::slice::from_raw_parts(self.base as *const u8, self.stride * locks.len())
+        };
+
+        Ok(Guard::new(locks, bytes))
+    }
+
+    pub(crate) fn iter(self, range: Range<usize>) -> IterRef<'a, L> {
+        IterRef { records: self, cur: range.start, end: range.end }
+    }
+
+    pub(crate) fn iter_mut(self, range: Range<usize>) -> IterMut<'a, L> where L: LenseMut {
+        IterMut { records: self, cur: range.start, end: range.end }
+    }
+
+    // Start of the record at `pos`
+    fn record(self, pos: usize) -> *mut u8 {
+        unsafe { self.base.add(self.stride * pos) }
+    }
+
+    fn locks(self, range: Range<usize>) -> Result<&'a [Cell<usize>], PoolError> {
+        let len = self.len();
+        match self.locks.get(range.clone()) {
+            Some(locks) => Ok(locks),
+            None => Err(PoolError::OutOfBounds { pos: ::std::cmp::max(range.start, len), len }),
+        }
+    }
+
+    fn lock_shared(self, range: Range<usize>) -> Result<&'a [Cell<usize>], PoolError> {
+        let locks = self.locks(range.clone())?;
+        for (i, lock) in locks.iter().enumerate() {
+            // Readers stop short of WRITER so they never look like a mutable guard
+            if lock.get() >= WRITER - 1 {
+                locks[..i].iter().for_each(release);
+                return Err(PoolError::Locked(range.start + i));
+            }
+            lock.set(lock.get() + 1);
         }
+        Ok(locks)
+    }
+
+    fn lock_exclusive(self, range: Range<usize>) -> Result<&'a [Cell<usize>], PoolError> {
+        let locks = self.locks(range.clone())?;
+        for (i, lock) in locks.iter().enumerate() {
+            if lock.get() != 0 {
+                locks[..i].iter().for_each(release);
+                return Err(PoolError::Locked(range.start + i));
+            }
+            lock.set(WRITER | 1);
+        }
+        Ok(locks)
+    }
+}
+
+impl<L: Lense, A: PoolWord> Default for SeekablePool<L, A> {
+    fn default() -> Self {
+        SeekablePool::aligned(0)
     }
 }
 
-// Guard the lense until it is dropped and then release the lock on the pool position
+/// Guard the lense until it is dropped and then release the locks on the pool positions
+///
+/// Any number of immutable guards may share a position; a mutable guard is exclusive. Guards
+/// over a whole record also know its lense type `L`, so they can be upgraded and downgraded.
+///
+/// The lense is only lent out by `lense` and `lense_mut`, borrowed from the guard itself, so
+/// nothing taken from it can outlive the lock.
+///
+/// ```compile_fail
+/// use lense::SeekablePool;
+///
+/// let pool = SeekablePool::<u16>::with_capacity(1);
+/// let guard = pool.get(0);
+/// let value: &u16 = guard.lense();
+/// drop(guard);
+/// // Another guard could now be writing to the record
+/// assert_eq!(*value, 0);
+/// ```
+///
+/// Neither can the lenses of two guards be swapped, which would leave each locking the other's
+/// record.
+///
+/// ```compile_fail
+/// use lense::SeekablePool;
+///
+/// let pool = SeekablePool::<u16>::with_capacity(2);
+/// let (mut a, mut b) = (pool.get_mut(0), pool.get_mut(1));
+/// ::std::mem::swap(&mut *a, &mut *b);
+/// ```
+///
+/// ```compile_fail
+/// use lense::SeekablePool;
+///
+/// let pool = SeekablePool::<u16>::with_capacity(2);
+/// let (mut a, mut b) = (pool.get_mut(0), pool.get_mut(1));
+/// let (mut x, mut y) = (a.lense_mut(), b.lense_mut());
+/// ::std::mem::swap(&mut x, &mut y);
+/// drop(a);
+/// *y = 1;
+/// ```
+pub struct Guard<'a, T, L = Projection> {
+    locks: &'a [Cell<usize>],
+    value: T,
+    // The guarded record when `L` is its lense, otherwise null
+    record: *mut u8,
+    marker: PhantomData<fn() -> L>,
+}
 
-pub struct Guard<'a, T> {
-    lock: &'a Cell<bool>,
-    value: T
+/// The `L` of guards that don't cover a whole record, such as those from `Guard::map`,
+/// `Guard::split` or a chunk. It isn't a lense, so these guards can't be upgraded or downgraded.
+///
+/// ```compile_fail
+/// use lense::{Guard, SeekablePool};
+///
+/// let pool = SeekablePool::<(u8, u8)>::with_capacity(1);
+/// let (a, _b) = Guard::split(pool.get_mut(0), |(x, y)| ((), (x, y)));
+/// a.downgrade();
+/// ```
+pub enum Projection {}
+
+// The guards lensing a whole record of type L
+type RefGuard<'a, L> = Guard<'a, <L as Mode<'a, IsRef>>::Return, L>;
+type MutGuard<'a, L> = Guard<'a, <L as Mode<'a, IsMut>>::Return, L>;
+
+// Guards sharing a position each hold one count, whether readers or pieces of a split writer
+fn share(lock: &Cell<usize>) {
+    lock.set(lock.get() + 1);
 }
 
-impl<'a, T> Drop for Guard<'a, T> {
-    fn drop(&mut self) {
-        self.lock.set(false);
+fn release(lock: &Cell<usize>) {
+    match lock.get() {
+        state if state == WRITER | 1 => lock.set(0),
+        state => lock.set(state - 1),
+    }
+}
+
+impl<'a, T> Guard<'a, T> {
+    fn new(locks: &'a [Cell<usize>], value: T) -> Self {
+        Guard { locks, value, record: ::std::ptr::null_mut(), marker: PhantomData }
+    }
+}
+
+impl<'a, T, L> Guard<'a, T, L> {
+    /// The guarded lense, borrowed from the guard
+    pub fn lense(&self) -> T::Ref<'_> where T: Reborrow {
+        self.value.reborrow()
+    }
+
+    /// The guarded lense, borrowed mutably from the guard
+    pub fn lense_mut(&mut self) -> T::Mut<'_> where T: ReborrowMut {
+        self.value.reborrow_mut()
+    }
+
+    /// Project the guarded lense, keeping its positions locked. Use as `Guard::map(guard, f)`.
+    ///
+    /// `f` may not capture borrows, so the lense it is handed can't escape the guard.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate lense;
+    /// # use lense::{Guard, SeekablePool, IsMut};
+    /// mk_lense_struct!{pub struct Alice: a: u32, d: u64,}
+    ///
+    /// fn field_d<'a>(pool: &'a SeekablePool<Alice<IsMut>>, pos: usize) -> Guard<'a, &'a u64> {
+    ///     Guard::map(pool.get(pos), |alice| alice.d)
+    /// }
+    /// # fn main() {
+    /// # let pool = SeekablePool::<Alice<IsMut>>::with_capacity(8);
+    /// assert_eq!(*field_d(&pool, 7).lense(), 0);
+    /// # }
+    /// ```
+    pub fn map<U, F>(guard: Self, f: F) -> Guard<'a, U> where F: FnOnce(T) -> U + 'static {
+        let (locks, value, _) = guard.into_parts();
+        // Release the positions should `f` panic
+        let held = Guard::new(locks, ());
+        let value = f(value);
+        ::std::mem::forget(held);
+        Guard::new(locks, value)
+    }
+
+    /// Project the guarded lense, or hand it back through `Err` to keep the original guard.
+    pub fn try_map<U, F>(guard: Self, f: F) -> Result<Guard<'a, U>, Self>
+        where F: FnOnce(T) -> Result<U, T> + 'static
+    {
+        let (locks, value, record) = guard.into_parts();
+        let held = Guard::new(locks, ());
+        let value = f(value);
+        ::std::mem::forget(held);
+        match value {
+            Ok(value) => Ok(Guard::new(locks, value)),
+            Err(value) => Err(Guard { locks, value, record, marker: PhantomData }),
+        }
+    }
+
+    /// Split the guarded lense in two, such as into disjoint fields, each keeping the positions
+    /// locked until both are dropped.
+    pub fn split<A, B, F>(guard: Self, f: F) -> (Guard<'a, A>, Guard<'a, B>)
+        where F: FnOnce(T) -> (A, B) + 'static
+    {
+        let (locks, value, _) = guard.into_parts();
+        let held = Guard::new(locks, ());
+        let (a, b) = f(value);
+        ::std::mem::forget(held);
+        locks.iter().for_each(share);
+        (Guard::new(locks, a), Guard::new(locks, b))
+    }
+
+    fn into_parts(self) -> (&'a [Cell<usize>], T, *mut u8) {
+        let guard = ::std::mem::ManuallyDrop::new(self);
+        // The guard is never dropped, so the value is read out exactly once
+        (guard.locks, unsafe { ::std::ptr::read(&guard.value) }, guard.record)
+    }
+}
+
+impl<'a, L: LenseMut> Guard<'a, <L as Mode<'a, IsRef>>::Return, L> {
+    /// Trade shared access to the record for mutable access without unlocking it in between.
+    /// Fails, handing the guard back, while other guards share the record.
+    ///
+    /// Lenses borrowed from the guard must be gone by then, so none alias the mutable lense.
+    ///
+    /// ```compile_fail
+    /// use lense::SeekablePool;
+    ///
+    /// let pool = SeekablePool::<u16>::with_capacity(1);
+    /// let reader = pool.get(0);
+    /// let value: &u16 = reader.lense();
+    /// let mut writer = reader.upgrade().ok().unwrap();
+    /// *writer.lense_mut() = *value + 1;
+    /// ```
+    pub fn upgrade(self) -> Result<Guard<'a, <L as Mode<'a, IsMut>>::Return, L>, Self> {
+        if self.locks[0].get() != 1 {
+            return Err(self);
+        }
+        let (locks, _, record) = self.into_parts();
+        locks[0].set(WRITER | 1);
+        let ptr = &mut Aligned::new(unsafe { // the guarded record
+            ::std::slice::from_raw_parts_mut(record, L::SIZE)
+        });
+        Ok(Guard { locks, value: L::lense_mut(ptr), record, marker: PhantomData })
     }
 }
 
-impl<'a, T> ::std::ops::Deref for Guard<'a, T> {
-    type Target = T;
+impl<'a, L: LenseMut> Guard<'a, <L as Mode<'a, IsMut>>::Return, L> {
+    /// Trade mutable access to the record for shared access without unlocking it in between.
+    pub fn downgrade(self) -> Guard<'a, <L as Mode<'a, IsRef>>::Return, L> {
+        let (locks, _, record) = self.into_parts();
+        locks[0].set(1);
+        let ptr = &mut Aligned::new(unsafe { // the guarded record
+            ::std::slice::from_raw_parts(record, L::SIZE)
+        });
+        Guard { locks, value: L::lense(ptr), record, marker: PhantomData }
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.value
+    /// Overwrite the bytes of the record through `f`, then lense it again. Lenses such as enums
+    /// take their shape from the bytes, so this is how a guard switches to another variant.
+    ///
+    /// When `f` panics or leaves bytes that don't lense, the record is put back as it was and the
+    /// guard keeps its old lense.
+    pub fn rewrite<F: FnOnce(&mut [u8])>(&mut self, f: F) -> Result<(), LenseError> {
+        // The guarded record, which no borrow of the guard can outlive
+        let bytes = || unsafe { ::std::slice::from_raw_parts_mut(self.record, L::SIZE) };
+        let mut restore = Restore { record: self.record, old: Some(bytes().to_vec()) };
+        f(bytes());
+        let value = L::try_lense_mut(&mut Aligned::new(bytes()))?;
+        restore.old = None;
+        self.value = value;
+        Ok(())
     }
 }
 
-impl<'a, T> ::std::ops::DerefMut for Guard<'a, T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.value
+// Puts back the bytes of a record that `Guard::rewrite` failed to lense
+struct Restore {
+    record: *mut u8,
+    old: Option<Vec<u8>>,
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        if let Some(old) = self.old.take() {
+            unsafe { ::std::ptr::copy_nonoverlapping(old.as_ptr(), self.record, old.len()) }
+        }
+    }
+}
+
+impl<'a, T, L> Drop for Guard<'a, T, L> {
+    fn drop(&mut self) {
+        self.locks.iter().for_each(release);
     }
 }
 
 // Should iterators be reserved for lense_vector?
 
-/// Iterate immutably over the pool's collection of lenses.
+/// Iterate immutably over a pool's or slice's collection of lenses.
 pub struct IterRef<'a, L: 'a + Lense> {
-    pool: &'a SeekablePool<L>,
+    records: Records<'a, L>,
     cur: usize,
+    end: usize,
 }
 
 impl<'a, L: Lense> Iterator for IterRef<'a, L> {
-    type Item = Guard<'a, <L as Mode<IsRef>>::Return>;
+    type Item = Guard<'a, <L as Mode<'a, IsRef>>::Return, L>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.cur < self.pool.state.len() {
-            let ret = self.pool.lense(self.cur);
+        self.next_indexed().map(|(_, guard)| guard)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        // Jump straight to the slot without touching the locks in between
+        self.cur = ::std::cmp::min(self.cur.saturating_add(n), self.end);
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Iteration stops early at a locked slot
+        (0, Some(self.end - self.cur))
+    }
+}
+
+impl<'a, L: Lense> LockingIter for IterRef<'a, L> {
+    type Guard = Guard<'a, <L as Mode<'a, IsRef>>::Return, L>;
+
+    fn next_slot(&mut self) -> Option<(usize, Result<Self::Guard, PoolError>)> {
+        if self.cur < self.end {
+            let pos = self.cur;
             self.cur += 1;
-            ret
+            Some((pos, self.records.try_get(pos)))
         } else { None }
     }
+
+    fn next_indexed(&mut self) -> Option<(usize, Self::Item)> {
+        match self.next_slot() {
+            Some((pos, Ok(guard))) => Some((pos, guard)),
+            _ => None,
+        }
+    }
 }
 
-impl<'a, L: Lense> ExactSizeIterator for IterRef<'a, L> {
-    fn len(&self) -> usize {
-        self.pool.state.capacity()
+impl<'a, L: Lense> DoubleEndedIterator for IterRef<'a, L> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.cur < self.end {
+            self.end -= 1;
+            self.records.try_get(self.end).ok()
+        } else { None }
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.end = ::std::cmp::max(self.end.saturating_sub(n), self.cur);
+        self.next_back()
     }
 }
 
-/// Iterate mutably over the pool's collection of lenses.
+/// Iterate mutably over a pool's or slice's collection of lenses.
 pub struct IterMut<'a, L: 'a + LenseMut> {
-    pool: &'a SeekablePool<L>,
+    records: Records<'a, L>,
     cur: usize,
+    end: usize,
 }
 
 impl<'a, L: LenseMut> Iterator for IterMut<'a, L> {
-    type Item = Guard<'a, <L as Mode<IsMut>>::Return>;
+    type Item = Guard<'a, <L as Mode<'a, IsMut>>::Return, L>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.cur < self.pool.state.len() {
-            let ret = self.pool.lense_mut(self.cur);
+        self.next_indexed().map(|(_, guard)| guard)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        // Jump straight to the slot without touching the locks in between
+        self.cur = ::std::cmp::min(self.cur.saturating_add(n), self.end);
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Iteration stops early at a locked slot
+        (0, Some(self.end - self.cur))
+    }
+}
+
+impl<'a, L: LenseMut> LockingIter for IterMut<'a, L> {
+    type Guard = Guard<'a, <L as Mode<'a, IsMut>>::Return, L>;
+
+    fn next_slot(&mut self) -> Option<(usize, Result<Self::Guard, PoolError>)> {
+        if self.cur < self.end {
+            let pos = self.cur;
             self.cur += 1;
-            ret
+            Some((pos, self.records.try_get_mut(pos)))
         } else { None }
     }
+
+    fn next_indexed(&mut self) -> Option<(usize, Self::Item)> {
+        match self.next_slot() {
+            Some((pos, Ok(guard))) => Some((pos, guard)),
+            _ => None,
+        }
+    }
 }
 
-impl<'a, L: LenseMut> ExactSizeIterator for IterMut<'a, L> {
-    fn len(&self) -> usize {
-        self.pool.state.capacity()
+impl<'a, L: LenseMut> DoubleEndedIterator for IterMut<'a, L> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.cur < self.end {
+            self.end -= 1;
+            self.records.try_get_mut(self.end).ok()
+        } else { None }
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.end = ::std::cmp::max(self.end.saturating_sub(n), self.cur);
+        self.next_back()
     }
 }
 
 #[cfg(test)]
 mod test {
-    use SeekablePool;
+    use {PoolError, SeekablePool};
 
     #[test]
     fn locking() {
         let pool = SeekablePool::<u8>::with_capacity(1);
-        if let Some(_guard) = pool.lense(0) {
-            assert!(pool.lense(0).is_none());
+        if let Ok(_guard) = pool.try_get_mut(0) {
+            assert_eq!(pool.try_get(0).err(), Some(PoolError::Locked(0)));
+            assert_eq!(pool.try_get_mut(0).err(), Some(PoolError::Locked(0)));
         } else { unreachable!() }
-        assert!(pool.lense(0).is_some());
+        assert!(pool.try_get_mut(0).is_ok());
+    }
+
+    #[test]
+    fn shared_locking() {
+        let pool = SeekablePool::<u8>::with_capacity(1);
+        let a = pool.try_get(0).unwrap();
+        let b = pool.try_get(0).unwrap();
+        assert_eq!(pool.try_get_mut(0).err(), Some(PoolError::Locked(0)));
+        drop(a);
+        assert_eq!(pool.try_get_mut(0).err(), Some(PoolError::Locked(0)));
+        drop(b);
+        assert!(pool.try_get_mut(0).is_ok());
+        assert_eq!(pool.state[0].get(), 0);
     }
 }
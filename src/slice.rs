@@ -0,0 +1,214 @@
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use {Lense, LenseMut, LenseError, Mode, IsRef, IsMut, PoolError};
+use aligned::{assert_fixed_size, Aligned};
+use seekable::{check_range, Guard, IterRef, IterMut, Records};
+
+// Number of records in `bytes`, checking it is a whole number of aligned records
+fn count<L: Lense>(bytes: &[u8]) -> Result<usize, LenseError> {
+    assert_fixed_size::<L>();
+    let uneven = LenseError::Uneven { size: L::SIZE, len: bytes.len() };
+    let len = match L::SIZE {
+        0 if bytes.is_empty() => 0,
+        size if size != 0 && bytes.len().is_multiple_of(size) => bytes.len() / size,
+        _ => return Err(uneven),
+    };
+    let misaligned = !(bytes.as_ptr() as usize).is_multiple_of(L::ALIGN)
+        || len > 1 && !L::SIZE.is_multiple_of(L::ALIGN);
+    if misaligned {
+        return Err(LenseError::Misaligned { align: L::ALIGN });
+    }
+    Ok(len)
+}
+
+/// Records of type L lensed in place from a borrowed buffer, such as a received datagram or a
+/// mapped region. Nothing can write through the buffer, so its lenses need no guards and live as
+/// long as the buffer itself.
+///
+/// ```
+/// use lense::LenseSlice;
+///
+/// let words = [1u32, 2, 3];
+/// let bytes = unsafe { ::std::slice::from_raw_parts(words.as_ptr() as *const u8, 12) };
+/// let slice = LenseSlice::<u32>::from_slice(bytes).unwrap();
+/// assert_eq!(slice.iter().map(|n| *n).sum::<u32>(), 6);
+/// assert!(LenseSlice::<u32>::from_slice(&bytes[..10]).is_err());
+/// ```
+pub struct LenseSlice<'buf, L> {
+    bytes: &'buf [u8],
+    len: usize,
+    marker: PhantomData<fn() -> L>,
+}
+
+impl<'buf, L: Lense> LenseSlice<'buf, L> {
+    /// View `bytes` as records of type L, failing when it isn't a whole number of aligned records
+    pub fn from_slice(bytes: &'buf [u8]) -> Result<Self, LenseError> {
+        Ok(LenseSlice { len: count::<L>(bytes)?, bytes, marker: PhantomData })
+    }
+
+    /// Number of records
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// True when the buffer holds no records
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Lense the record at `pos`, failing when it is out of bounds.
+    pub fn try_get(&self, pos: usize) -> Result<<L as Mode<'buf, IsRef>>::Return, PoolError> {
+        if pos < self.len {
+            Ok(L::try_lense_record(&mut Aligned::new(&self.bytes[pos * L::SIZE..]))?)
+        } else {
+            Err(PoolError::OutOfBounds { pos, len: self.len })
+        }
+    }
+
+    /// Lense the record at `pos`. Panics when it is out of bounds.
+    pub fn get(&self, pos: usize) -> <L as Mode<'buf, IsRef>>::Return {
+        self.try_get(pos).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Iterate over the buffer's collection of lenses.
+    pub fn iter(&self)
+        -> impl DoubleEndedIterator<Item = <L as Mode<'buf, IsRef>>::Return>
+               + ExactSizeIterator + 'buf
+        where L: 'buf
+    {
+        self.range(0..self.len)
+    }
+
+    /// Iterate over the lenses in `range`. Panics when the range is out of bounds.
+    pub fn range(&self, range: Range<usize>)
+        -> impl DoubleEndedIterator<Item = <L as Mode<'buf, IsRef>>::Return>
+               + ExactSizeIterator + 'buf
+        where L: 'buf
+    {
+        check_range(&range, self.len);
+        let bytes = self.bytes;
+        range.map(move |pos| L::lense(&mut Aligned::new(&bytes[pos * L::SIZE..])))
+    }
+
+    /// The underlying buffer
+    pub fn as_bytes(&self) -> &'buf [u8] {
+        self.bytes
+    }
+}
+
+/// Records of type L lensed in place from a mutably borrowed buffer, locked on borrows just like
+/// a `SeekablePool`. The lock table is the only allocation; the records are never copied.
+///
+/// ```
+/// use lense::LenseSliceMut;
+///
+/// let mut words = [0u32; 4];
+/// let bytes = unsafe { ::std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, 16) };
+/// let mut slice = LenseSliceMut::<u32>::from_slice(bytes).unwrap();
+/// for mut guard in slice.iter_mut() {
+///     *guard.lense_mut() = 7;
+/// }
+/// let guard = slice.get_mut(1);
+/// assert!(slice.try_get(1).is_err());
+/// drop(guard);
+/// assert_eq!(words, [7; 4]);
+/// ```
+pub struct LenseSliceMut<'buf, L> {
+    // Derived once from the borrowed buffer and written through shared guards
+    base: *mut u8,
+    state: Vec<Cell<usize>>,
+    marker: PhantomData<Borrowed<'buf, L>>,
+}
+
+// Borrows the buffer mutably while only ever producing records of type L
+type Borrowed<'buf, L> = (&'buf mut [u8], fn() -> L);
+
+impl<'buf, L: Lense> LenseSliceMut<'buf, L> {
+    /// View `bytes` as records of type L, failing when it isn't a whole number of aligned records
+    pub fn from_slice(bytes: &'buf mut [u8]) -> Result<Self, LenseError> {
+        let len = count::<L>(bytes)?;
+        Ok(LenseSliceMut {
+            base: bytes.as_mut_ptr(),
+            state: vec![Cell::new(0); len],
+            marker: PhantomData,
+        })
+    }
+
+    /// Number of records
+    pub fn len(&self) -> usize {
+        self.state.len()
+    }
+
+    /// True when the buffer holds no records
+    pub fn is_empty(&self) -> bool {
+        self.state.is_empty()
+    }
+
+    /// Lense the record at `pos`, failing when it is out of bounds or lensed mutably.
+    pub fn try_get<'a>(&'a self, pos: usize)
+        -> Result<Guard<'a, <L as Mode<'a, IsRef>>::Return, L>, PoolError>
+    {
+        self.records().try_get(pos)
+    }
+
+    /// Lense the record at `pos` mutably, failing when it is out of bounds or already locked.
+    pub fn try_get_mut<'a>(&'a self, pos: usize)
+        -> Result<Guard<'a, <L as Mode<'a, IsMut>>::Return, L>, PoolError>
+        where L: LenseMut
+    {
+        self.records().try_get_mut(pos)
+    }
+
+    /// Lense the record at `pos`. Panics when it is out of bounds or lensed mutably.
+    pub fn get<'a>(&'a self, pos: usize) -> Guard<'a, <L as Mode<'a, IsRef>>::Return, L> {
+        self.try_get(pos).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Lense the record at `pos` mutably. Panics when it is out of bounds or already locked.
+    pub fn get_mut<'a>(&'a self, pos: usize) -> Guard<'a, <L as Mode<'a, IsMut>>::Return, L>
+        where L: LenseMut
+    {
+        self.try_get_mut(pos).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Iterate immutably over the buffer's collection of lenses, ending at the first slot that
+    /// is lensed mutably. See `LockingIter` for other ways to handle locked slots.
+    pub fn iter(&self) -> IterRef<'_, L> {
+        self.records().iter(0..self.len())
+    }
+
+    /// Iterate mutably over the buffer's collection of lenses.
+    pub fn iter_mut(&mut self) -> IterMut<'_, L> where L: LenseMut {
+        self.records().iter_mut(0..self.len())
+    }
+
+    /// Iterate immutably over the lenses in `range`. Panics when the range is out of bounds.
+    pub fn range(&self, range: Range<usize>) -> IterRef<'_, L> {
+        check_range(&range, self.len());
+        self.records().iter(range)
+    }
+
+    /// Iterate mutably over the lenses in `range`. Panics when the range is out of bounds.
+    pub fn range_mut(&mut self, range: Range<usize>) -> IterMut<'_, L> where L: LenseMut {
+        check_range(&range, self.len());
+        self.records().iter_mut(range)
+    }
+
+    /// The underlying buffer, locked like an immutable guard over every record. Panics while
+    /// any record is lensed mutably.
+    pub fn as_bytes(&self) -> Guard<'_, &[u8]> {
+        self.records().try_get_bytes()
+            .unwrap_or_else(|_| panic!("Slice has outstanding mutable guards"))
+    }
+
+    /// The underlying buffer
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        unsafe { ::std::slice::from_raw_parts_mut(self.base, self.len() * L::SIZE) }
+    }
+
+    fn records(&self) -> Records<'_, L> {
+        Records::new(self.base, &self.state, L::SIZE)
+    }
+}
@@ -0,0 +1,196 @@
+use std::marker::PhantomData;
+use std::str;
+
+use {DiceRef, DiceMut, Lense, LenseMut, LenseError, Reborrow, ReborrowMut};
+use vec::Length;
+
+fn utf8(bytes: &[u8]) -> Result<&str, LenseError> {
+    str::from_utf8(bytes).map_err(|e| LenseError::InvalidUtf8 { valid_up_to: e.valid_up_to() })
+}
+
+// Length of `bytes` once trailing `pad` bytes are dropped
+fn trimmed(bytes: &[u8], pad: u8) -> usize {
+    bytes.iter().rposition(|&b| b != pad).map_or(0, |i| i + 1)
+}
+
+/// A `Len` prefix followed by that many bytes, lensed as `&[u8]`
+///
+/// The mutable lense is `&mut [u8]`: the length is fixed by the record, so contents can be
+/// overwritten in place with `copy_from_slice` when the new value is the same length. Like
+/// `LenseVec`, records holding one aren't `FIXED_SIZE` and can't be kept in a pool or slice.
+pub struct LenseBytes<Len>(PhantomData<Len>);
+
+impl<Len: Length> Lense for LenseBytes<Len> {
+    type Ref<'a> = &'a [u8];
+
+    const SIZE: usize = Len::SIZE;
+    const ALIGN: usize = Len::ALIGN;
+    const FIXED_SIZE: bool = false;
+
+    #[inline]
+    fn try_lense<'a, Buf: DiceRef<'a>>(buf: &mut Buf) -> Result<Self::Ref<'a>, LenseError> {
+        let len = buf.try_dice::<Len>()?.to_len();
+        buf.try_dice_bytes(len)
+    }
+}
+
+impl<Len: Length> LenseMut for LenseBytes<Len> {
+    type Mut<'a> = &'a mut [u8];
+
+    #[inline]
+    fn try_lense_mut<'a, Buf: DiceMut<'a>>(buf: &mut Buf) -> Result<Self::Mut<'a>, LenseError> {
+        let len = buf.try_dice::<Len>()?.to_len();
+        buf.try_dice_bytes_mut(len)
+    }
+}
+
+/// A `Len` prefix followed by that many bytes of UTF-8, lensed as `&str`
+///
+/// Lensing fails with `LenseError::InvalidUtf8` rather than yield a broken string. Records
+/// holding one aren't `FIXED_SIZE`, see `LenseBytes`.
+///
+/// ```
+/// # #[macro_use] extern crate lense;
+/// # use lense::{LenseMut, LenseStr, IsMut};
+/// mk_lense_struct!{pub struct User: id: u8, name: LenseStr<u8>,}
+///
+/// # fn main() {
+/// let mut bytes = *b"\x07\x05alice";
+/// let mut user = User::<IsMut>::lense_mut(&mut &mut bytes[..]);
+/// assert_eq!(&*user.name, "alice");
+/// user.name.set("bobby").unwrap();
+/// assert!(user.name.set("bob").is_err());
+/// # drop(user);
+/// assert_eq!(&bytes, b"\x07\x05bobby");
+/// # }
+/// ```
+pub struct LenseStr<Len>(PhantomData<Len>);
+
+impl<Len: Length> Lense for LenseStr<Len> {
+    type Ref<'a> = &'a str;
+
+    const SIZE: usize = Len::SIZE;
+    const ALIGN: usize = Len::ALIGN;
+    const FIXED_SIZE: bool = false;
+
+    #[inline]
+    fn try_lense<'a, Buf: DiceRef<'a>>(buf: &mut Buf) -> Result<Self::Ref<'a>, LenseError> {
+        utf8(LenseBytes::<Len>::try_lense(buf)?)
+    }
+}
+
+impl<Len: Length> LenseMut for LenseStr<Len> {
+    type Mut<'a> = StrMut<'a>;
+
+    #[inline]
+    fn try_lense_mut<'a, Buf: DiceMut<'a>>(buf: &mut Buf) -> Result<Self::Mut<'a>, LenseError> {
+        let bytes = LenseBytes::<Len>::try_lense_mut(buf)?;
+        utf8(bytes)?;
+        Ok(StrMut { bytes, pad: None })
+    }
+}
+
+/// `N` bytes of UTF-8 padded at the end with `PAD`, lensed as `&str` without the padding
+///
+/// The padding is NUL by default; space padding is `FixedStr<N, b' '>`. Panics when lensed
+/// with a padding byte that isn't ASCII.
+///
+/// ```
+/// # #[macro_use] extern crate lense;
+/// # use lense::{FixedStr, Lense, IsRef};
+/// mk_lense_struct!{pub struct Entry: key: FixedStr<8>, label: FixedStr<6, b' '>,}
+///
+/// # fn main() {
+/// let entry = Entry::<IsRef>::lense(&mut &b"key\0\0\0\0\0ok    "[..]);
+/// assert_eq!((entry.key, entry.label), ("key", "ok"));
+/// # }
+/// ```
+pub struct FixedStr<const N: usize, const PAD: u8 = 0>([u8; N]);
+
+impl<const N: usize, const PAD: u8> Lense for FixedStr<N, PAD> {
+    type Ref<'a> = &'a str;
+
+    const SIZE: usize = N;
+    const ALIGN: usize = 1;
+
+    #[inline]
+    fn try_lense<'a, Buf: DiceRef<'a>>(buf: &mut Buf) -> Result<Self::Ref<'a>, LenseError> {
+        assert!(PAD.is_ascii(), "FixedStr padding must be ASCII");
+        let bytes = buf.try_dice_bytes(N)?;
+        utf8(&bytes[..trimmed(bytes, PAD)])
+    }
+}
+
+impl<const N: usize, const PAD: u8> LenseMut for FixedStr<N, PAD> {
+    type Mut<'a> = StrMut<'a>;
+
+    #[inline]
+    fn try_lense_mut<'a, Buf: DiceMut<'a>>(buf: &mut Buf) -> Result<Self::Mut<'a>, LenseError> {
+        assert!(PAD.is_ascii(), "FixedStr padding must be ASCII");
+        let bytes = buf.try_dice_bytes_mut(N)?;
+        utf8(&bytes[..trimmed(bytes, PAD)])?;
+        Ok(StrMut { bytes, pad: Some(PAD) })
+    }
+}
+
+/// A string lensed mutably in place. Dereferences to the current `str`.
+///
+/// Prefixed strings keep the length the record was written with, so new contents must match it;
+/// padded strings take anything up to their capacity and pad the rest.
+pub struct StrMut<'a> {
+    // Valid UTF-8 followed by padding, checked when lensed and kept so by `set`
+    bytes: &'a mut [u8],
+    pad: Option<u8>,
+}
+
+impl<'a> StrMut<'a> {
+    /// Bytes available to the string
+    pub fn capacity(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Overwrite the string in place, failing when `s` doesn't fit
+    pub fn set(&mut self, s: &str) -> Result<(), LenseError> {
+        let fits = match self.pad {
+            Some(_) => s.len() <= self.bytes.len(),
+            None => s.len() == self.bytes.len(),
+        };
+        if !fits {
+            return Err(LenseError::DoesNotFit { capacity: self.bytes.len(), len: s.len() });
+        }
+        let (head, tail) = self.bytes.split_at_mut(s.len());
+        head.copy_from_slice(s.as_bytes());
+        for byte in tail {
+            *byte = self.pad.unwrap_or(0);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ::std::ops::Deref for StrMut<'a> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        let len = match self.pad {
+            Some(pad) => trimmed(self.bytes, pad),
+            None => self.bytes.len(),
+        };
+        unsafe { str::from_utf8_unchecked(&self.bytes[..len]) }
+    }
+}
+
+impl<'a> Reborrow for StrMut<'a> {
+    type Ref<'g> = &'g str where Self: 'g;
+
+    fn reborrow(&self) -> Self::Ref<'_> {
+        self
+    }
+}
+
+impl<'a> ReborrowMut for StrMut<'a> {
+    type Mut<'g> = StrMut<'g> where Self: 'g;
+
+    fn reborrow_mut(&mut self) -> Self::Mut<'_> {
+        StrMut { bytes: self.bytes, pad: self.pad }
+    }
+}
@@ -0,0 +1,386 @@
+use std::cell::UnsafeCell;
+use std::ops::Range;
+use std::sync::{Condvar, Mutex, PoisonError};
+use std::sync::atomic::{fence, AtomicUsize, Ordering};
+
+use {Lense, LenseMut, LockingIter, Mode, IsRef, IsMut, PoolError, Reborrow, ReborrowMut};
+use aligned::{assert_fixed_size, Aligned};
+use seekable::check_range;
+
+const WRITER: usize = usize::MAX;
+
+/// A fixed size `SeekablePool` whose slots may be locked from many threads at once.
+///
+/// Each record is guarded by an atomic reader/writer lock. Guards are acquired with `try_get`
+/// (never waits), `spin_get` (busy waits) or `get` (sleeps until the slot is released), and
+/// likewise for the `_mut` variants.
+pub struct SyncPool<L: Lense> {
+    // Backing u64 pool, only reached through slot locks
+    pool: Vec<UnsafeCell<u64>>,
+    // Lock state: the number of shared guards, or WRITER while a mutable guard is held
+    state: Vec<AtomicUsize>,
+    // Threads sleeping in `get` and `get_mut`
+    waiters: Waiters,
+    // The pool is optimized for this type
+    marker: ::std::marker::PhantomData<fn() -> L>,
+}
+
+// Records are plain bytes and every access goes through a slot lock
+unsafe impl<L: Lense> Sync for SyncPool<L> {}
+
+struct Waiters {
+    count: AtomicUsize,
+    mutex: Mutex<()>,
+    released: Condvar,
+}
+
+impl Waiters {
+    fn notify(&self) {
+        if self.count.load(Ordering::SeqCst) > 0 {
+            let _lock = self.mutex.lock().unwrap_or_else(PoisonError::into_inner);
+            self.released.notify_all();
+        }
+    }
+}
+
+impl<L: Lense> SyncPool<L> {
+    /// Prepare a collection of `cap` zeroed records of type L
+    pub fn with_capacity(cap: usize) -> Self {
+        assert_fixed_size::<L>();
+        SyncPool {
+            pool: (0..(cap * L::SIZE).div_ceil(8)).map(|_| UnsafeCell::new(0)).collect(),
+            state: (0..cap).map(|_| AtomicUsize::new(0)).collect(),
+            waiters: Waiters {
+                count: AtomicUsize::new(0),
+                mutex: Mutex::new(()),
+                released: Condvar::new(),
+            },
+            marker: ::std::marker::PhantomData,
+        }
+    }
+
+    /// Number of records
+    pub fn len(&self) -> usize {
+        self.state.len()
+    }
+
+    /// True when the collection holds no records
+    pub fn is_empty(&self) -> bool {
+        self.state.is_empty()
+    }
+
+    /// The bytes of every record
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        unsafe { // Vec<u64> -> &mut [u8]
+            ::std::slice::from_raw_parts_mut(self.pool.as_mut_ptr() as *mut u8,
+                                             self.len() * L::SIZE)
+        }
+    }
+
+    /// Lense the record at `pos`, failing when it is out of bounds or lensed mutably.
+    pub fn try_get<'a>(&'a self, pos: usize)
+        -> Result<SyncGuard<'a, <L as Mode<'a, IsRef>>::Return>, PoolError>
+    {
+        let lock = self.lock_shared(pos)?;
+        let ptr = &mut Aligned::new(unsafe { // &self[L::SIZE * pos .. L::SIZE]
+            ::std::slice::from_raw_parts(self.slot(pos), L::SIZE)
+        });
+        let value = L::try_lense_record(ptr).inspect_err(|_| self.unlock(lock))?;
+
+        Ok(SyncGuard { lock, waiters: &self.waiters, value })
+    }
+
+    /// Lense the record at `pos` mutably, failing when it is out of bounds or already locked.
+    pub fn try_get_mut<'a>(&'a self, pos: usize)
+        -> Result<SyncGuard<'a, <L as Mode<'a, IsMut>>::Return>, PoolError>
+        where L: LenseMut
+    {
+        let lock = self.lock_exclusive(pos)?;
+        let ptr = &mut Aligned::new(unsafe { // &mut self[L::SIZE * pos .. L::SIZE]
+            ::std::slice::from_raw_parts_mut(self.slot(pos), L::SIZE)
+        });
+        let value = L::try_lense_record_mut(ptr).inspect_err(|_| self.unlock(lock))?;
+
+        Ok(SyncGuard { lock, waiters: &self.waiters, value })
+    }
+
+    /// Lense the record at `pos`, busy waiting while it is lensed mutably. Panics when it is out
+    /// of bounds.
+    pub fn spin_get<'a>(&'a self, pos: usize) -> SyncGuard<'a, <L as Mode<'a, IsRef>>::Return> {
+        spin(|| self.try_get(pos))
+    }
+
+    /// Lense the record at `pos` mutably, busy waiting while it is locked. Panics when it is out
+    /// of bounds.
+    pub fn spin_get_mut<'a>(&'a self, pos: usize)
+        -> SyncGuard<'a, <L as Mode<'a, IsMut>>::Return>
+        where L: LenseMut
+    {
+        spin(|| self.try_get_mut(pos))
+    }
+
+    /// Lense the record at `pos`, sleeping while it is lensed mutably. Panics when it is out of
+    /// bounds.
+    pub fn get<'a>(&'a self, pos: usize) -> SyncGuard<'a, <L as Mode<'a, IsRef>>::Return> {
+        self.block(|| self.try_get(pos))
+    }
+
+    /// Lense the record at `pos` mutably, sleeping while it is locked. Panics when it is out of
+    /// bounds.
+    pub fn get_mut<'a>(&'a self, pos: usize) -> SyncGuard<'a, <L as Mode<'a, IsMut>>::Return>
+        where L: LenseMut
+    {
+        self.block(|| self.try_get_mut(pos))
+    }
+
+    /// Iterate immutably over the pool's collection of lenses, waiting for slots that are lensed
+    /// mutably. See `LockingIter` for other ways to handle locked slots.
+    pub fn iter(&self) -> SyncIterRef<'_, L> {
+        SyncIterRef { pool: self, cur: 0, end: self.len() }
+    }
+
+    /// Iterate mutably over the pool's collection of lenses, waiting for slots that are locked.
+    pub fn iter_mut(&self) -> SyncIterMut<'_, L> where L: LenseMut {
+        SyncIterMut { pool: self, cur: 0, end: self.len() }
+    }
+
+    /// Iterate immutably over the lenses in `range`. Panics when the range is out of bounds.
+    pub fn range(&self, range: Range<usize>) -> SyncIterRef<'_, L> {
+        check_range(&range, self.len());
+        SyncIterRef { pool: self, cur: range.start, end: range.end }
+    }
+
+    /// Iterate mutably over the lenses in `range`. Panics when the range is out of bounds.
+    pub fn range_mut(&self, range: Range<usize>) -> SyncIterMut<'_, L> where L: LenseMut {
+        check_range(&range, self.len());
+        SyncIterMut { pool: self, cur: range.start, end: range.end }
+    }
+
+    fn slot(&self, pos: usize) -> *mut u8 {
+        unsafe { (self.pool.as_ptr() as *mut u8).add(L::SIZE * pos) }
+    }
+
+    fn lock_shared(&self, pos: usize) -> Result<&AtomicUsize, PoolError> {
+        let lock = self.state.get(pos)
+            .ok_or(PoolError::OutOfBounds { pos, len: self.len() })?;
+        let mut readers = lock.load(Ordering::Relaxed);
+        // WRITER - 1 readers would overflow into the writer state
+        while readers < WRITER - 1 {
+            match lock.compare_exchange_weak(readers, readers + 1,
+                                             Ordering::Acquire, Ordering::Relaxed) {
+                Ok(..) => return Ok(lock),
+                Err(actual) => readers = actual,
+            }
+        }
+        Err(PoolError::Locked(pos))
+    }
+
+    fn lock_exclusive(&self, pos: usize) -> Result<&AtomicUsize, PoolError> {
+        let lock = self.state.get(pos)
+            .ok_or(PoolError::OutOfBounds { pos, len: self.len() })?;
+        match lock.compare_exchange(0, WRITER, Ordering::Acquire, Ordering::Relaxed) {
+            Ok(..) => Ok(lock),
+            Err(..) => Err(PoolError::Locked(pos)),
+        }
+    }
+
+    // Release a lock taken for a guard that was never built
+    fn unlock(&self, lock: &AtomicUsize) {
+        drop(SyncGuard { lock, waiters: &self.waiters, value: () });
+    }
+
+    fn block<G, F>(&self, mut acquire: F) -> G where F: FnMut() -> Result<G, PoolError> {
+        loop {
+            match acquire() {
+                Ok(guard) => return guard,
+                Err(PoolError::Locked(..)) => (),
+                Err(e) => panic!("{}", e),
+            }
+
+            let lock = self.waiters.mutex.lock().unwrap_or_else(PoisonError::into_inner);
+            self.waiters.count.fetch_add(1, Ordering::SeqCst);
+            // Retry once counted so a release between the attempts above isn't missed. The fence
+            // orders the count before the retry's relaxed read of the lock, so either the retry
+            // sees the release or the releaser sees the count and notifies.
+            fence(Ordering::SeqCst);
+            let retry = acquire();
+            if let Err(PoolError::Locked(..)) = retry {
+                drop(self.waiters.released.wait(lock).unwrap_or_else(PoisonError::into_inner));
+            }
+            self.waiters.count.fetch_sub(1, Ordering::SeqCst);
+            match retry {
+                Ok(guard) => return guard,
+                Err(PoolError::Locked(..)) => (),
+                Err(e) => panic!("{}", e),
+            }
+        }
+    }
+}
+
+fn spin<G, F>(mut acquire: F) -> G where F: FnMut() -> Result<G, PoolError> {
+    loop {
+        match acquire() {
+            Ok(guard) => return guard,
+            Err(PoolError::Locked(..)) => ::std::hint::spin_loop(),
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+/// Guard the lense until it is dropped and then release the lock on the pool position,
+/// waking any threads waiting for it
+///
+/// Like `Guard`, the lense is only lent out borrowed from the guard.
+pub struct SyncGuard<'a, T> {
+    lock: &'a AtomicUsize,
+    waiters: &'a Waiters,
+    value: T,
+}
+
+impl<'a, T> SyncGuard<'a, T> {
+    /// The guarded lense, borrowed from the guard
+    pub fn lense(&self) -> T::Ref<'_> where T: Reborrow {
+        self.value.reborrow()
+    }
+
+    /// The guarded lense, borrowed mutably from the guard
+    pub fn lense_mut(&mut self) -> T::Mut<'_> where T: ReborrowMut {
+        self.value.reborrow_mut()
+    }
+}
+
+impl<'a, T> Drop for SyncGuard<'a, T> {
+    fn drop(&mut self) {
+        // A writer is alone on its position, so the lock state tells us which kind we are
+        if self.lock.load(Ordering::Relaxed) == WRITER {
+            self.lock.store(0, Ordering::SeqCst);
+        } else {
+            self.lock.fetch_sub(1, Ordering::SeqCst);
+        }
+        self.waiters.notify();
+    }
+}
+
+/// Iterate immutably over the pool's collection of lenses, waiting for locked slots.
+pub struct SyncIterRef<'a, L: 'a + Lense> {
+    pool: &'a SyncPool<L>,
+    cur: usize,
+    end: usize,
+}
+
+impl<'a, L: Lense> Iterator for SyncIterRef<'a, L> {
+    type Item = SyncGuard<'a, <L as Mode<'a, IsRef>>::Return>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_indexed().map(|(_, guard)| guard)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        // Jump straight to the slot without touching the locks in between
+        self.cur = ::std::cmp::min(self.cur.saturating_add(n), self.end);
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.cur;
+        (len, Some(len))
+    }
+}
+
+impl<'a, L: Lense> ExactSizeIterator for SyncIterRef<'a, L> {}
+
+impl<'a, L: Lense> DoubleEndedIterator for SyncIterRef<'a, L> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.cur < self.end {
+            self.end -= 1;
+            Some(self.pool.get(self.end))
+        } else { None }
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.end = ::std::cmp::max(self.end.saturating_sub(n), self.cur);
+        self.next_back()
+    }
+}
+
+impl<'a, L: Lense> LockingIter for SyncIterRef<'a, L> {
+    type Guard = SyncGuard<'a, <L as Mode<'a, IsRef>>::Return>;
+
+    fn next_slot(&mut self) -> Option<(usize, Result<Self::Guard, PoolError>)> {
+        if self.cur < self.end {
+            let pos = self.cur;
+            self.cur += 1;
+            Some((pos, self.pool.try_get(pos)))
+        } else { None }
+    }
+
+    fn next_indexed(&mut self) -> Option<(usize, Self::Item)> {
+        if self.cur < self.end {
+            let pos = self.cur;
+            self.cur += 1;
+            Some((pos, self.pool.get(pos)))
+        } else { None }
+    }
+}
+
+/// Iterate mutably over the pool's collection of lenses, waiting for locked slots.
+pub struct SyncIterMut<'a, L: 'a + LenseMut> {
+    pool: &'a SyncPool<L>,
+    cur: usize,
+    end: usize,
+}
+
+impl<'a, L: LenseMut> Iterator for SyncIterMut<'a, L> {
+    type Item = SyncGuard<'a, <L as Mode<'a, IsMut>>::Return>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_indexed().map(|(_, guard)| guard)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        // Jump straight to the slot without touching the locks in between
+        self.cur = ::std::cmp::min(self.cur.saturating_add(n), self.end);
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.cur;
+        (len, Some(len))
+    }
+}
+
+impl<'a, L: LenseMut> ExactSizeIterator for SyncIterMut<'a, L> {}
+
+impl<'a, L: LenseMut> DoubleEndedIterator for SyncIterMut<'a, L> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.cur < self.end {
+            self.end -= 1;
+            Some(self.pool.get_mut(self.end))
+        } else { None }
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.end = ::std::cmp::max(self.end.saturating_sub(n), self.cur);
+        self.next_back()
+    }
+}
+
+impl<'a, L: LenseMut> LockingIter for SyncIterMut<'a, L> {
+    type Guard = SyncGuard<'a, <L as Mode<'a, IsMut>>::Return>;
+
+    fn next_slot(&mut self) -> Option<(usize, Result<Self::Guard, PoolError>)> {
+        if self.cur < self.end {
+            let pos = self.cur;
+            self.cur += 1;
+            Some((pos, self.pool.try_get_mut(pos)))
+        } else { None }
+    }
+
+    fn next_indexed(&mut self) -> Option<(usize, Self::Item)> {
+        if self.cur < self.end {
+            let pos = self.cur;
+            self.cur += 1;
+            Some((pos, self.pool.get_mut(pos)))
+        } else { None }
+    }
+}
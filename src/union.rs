@@ -0,0 +1,144 @@
+/// Create an untagged union of lense-safe types sharing the same bytes
+///
+/// Nothing in the buffer records which member is stored, so each member is declared with a pair
+/// of accessors and the caller picks the interpretation, typically from an earlier field. The
+/// union occupies the bytes of its largest member and is aligned for the strictest.
+///
+/// ```
+/// # #[macro_use] extern crate lense;
+/// # use lense::{Be, Lense, LenseMut, IsRef, IsMut};
+/// mk_lense_union!{pub union Addr:
+///     as_v4, as_v4_mut: [u8; 4],
+///     /// Members may be documented
+///     as_v6, as_v6_mut: [Be<u16>; 8],
+/// }
+///
+/// # fn main() {
+/// let mut words = [0u16; 8];
+/// let bytes = unsafe { ::std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, 16) };
+/// assert_eq!(Addr::<IsRef>::size(), 16);
+/// {
+///     let mut addr = Addr::<IsMut>::lense_mut(&mut &mut bytes[..]);
+///     *addr.as_v4_mut()[0] = 0xfe;
+///     addr.as_v6_mut()[7].set(1);
+/// }
+/// let addr = Addr::<IsRef>::lense(&mut &bytes[..]);
+/// assert_eq!((*addr.as_v4()[0], addr.as_v6()[0].get()), (0xfe, 0xfe00));
+/// assert_eq!(addr.as_v6()[7].get(), 1);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! mk_lense_union {
+    // Bytes shared by the members: the largest member
+    (@slot $($ty:ty),*) => { $crate::max_of(&[$(<$ty as $crate::Lense>::SIZE),*]) };
+
+    ($(#[$meta:meta])* $vis:vis union $ident:ident:
+        $($(#[$attr:meta])* $get:ident, $get_mut:ident: $ty:ty),* $(,)*
+    ) => {
+        $(#[$meta])*
+        $vis struct $ident<'a, M>
+            where $crate::Block<u8, { $crate::mk_lense_union!(@slot $($ty),*) }>:
+                  $crate::Mode<'a, M>
+        {
+            bytes: <$crate::Block<u8, { $crate::mk_lense_union!(@slot $($ty),*) }>
+                    as $crate::Mode<'a, M>>::Return,
+        }
+
+        impl<'x> $ident<'x, $crate::IsRef> {
+            $(
+                $(#[$attr])*
+                #[inline]
+                #[allow(dead_code)]
+                pub fn $get(&self) -> <$ty as $crate::Mode<'x, $crate::IsRef>>::Return {
+                    <$ty as $crate::Lense>::lense(&mut $crate::Aligned::new(&self.bytes[..]))
+                }
+            )*
+        }
+
+        impl<'x> $ident<'x, $crate::IsMut> {
+            $(
+                $(#[$attr])*
+                #[inline]
+                #[allow(dead_code)]
+                pub fn $get(&self) -> <$ty as $crate::Mode<'_, $crate::IsRef>>::Return {
+                    <$ty as $crate::Lense>::lense(&mut $crate::Aligned::new(&self.bytes[..]))
+                }
+
+                $(#[$attr])*
+                #[inline]
+                #[allow(dead_code)]
+                pub fn $get_mut(&mut self) -> <$ty as $crate::Mode<'_, $crate::IsMut>>::Return {
+                    let bytes = &mut self.bytes[..];
+                    <$ty as $crate::LenseMut>::lense_mut(&mut $crate::Aligned::new(bytes))
+                }
+            )*
+        }
+
+        impl<'x, M> $crate::Lense for $ident<'x, M>
+            where $crate::Block<u8, { $crate::mk_lense_union!(@slot $($ty),*) }>:
+                  $crate::Mode<'x, M>
+        {
+            type Ref<'a> = $ident<'a, $crate::IsRef>;
+
+            const SIZE: usize = $crate::composite_size(&[
+                ($crate::mk_lense_union!(@slot $($ty),*), <Self as $crate::Lense>::ALIGN),
+            ]);
+            const ALIGN: usize = $crate::max_of(&[1 $(, <$ty as $crate::Lense>::ALIGN)*]);
+
+            #[inline]
+            fn try_lense<'a, Buf: $crate::DiceRef<'a>>(buf: &mut Buf)
+                -> Result<Self::Ref<'a>, $crate::LenseError>
+            {
+                $($crate::assert_fixed_size::<$ty>();)*
+                buf.pad_to(<Self as $crate::Lense>::ALIGN)?;
+                let bytes = <$crate::Block<u8, { $crate::mk_lense_union!(@slot $($ty),*) }>
+                            as $crate::Lense>::try_lense(buf)?;
+                buf.pad_to(<Self as $crate::Lense>::ALIGN)?;
+                Ok($ident::<$crate::IsRef> { bytes })
+            }
+        }
+
+        impl<'x> $crate::LenseMut for $ident<'x, $crate::IsMut> {
+            type Mut<'a> = $ident<'a, $crate::IsMut>;
+
+            #[inline]
+            fn try_lense_mut<'a, Buf: $crate::DiceMut<'a>>(buf: &mut Buf)
+                -> Result<Self::Mut<'a>, $crate::LenseError>
+            {
+                $($crate::assert_fixed_size::<$ty>();)*
+                buf.pad_to(<Self as $crate::Lense>::ALIGN)?;
+                let bytes = <$crate::Block<u8, { $crate::mk_lense_union!(@slot $($ty),*) }>
+                            as $crate::LenseMut>::try_lense_mut(buf)?;
+                buf.pad_to(<Self as $crate::Lense>::ALIGN)?;
+                Ok($ident { bytes })
+            }
+        }
+
+        impl<'x> $crate::Reborrow for $ident<'x, $crate::IsRef> {
+            type Ref<'g> = $ident<'g, $crate::IsRef> where Self: 'g;
+
+            #[inline]
+            fn reborrow(&self) -> Self::Ref<'_> {
+                $ident { bytes: self.bytes }
+            }
+        }
+
+        impl<'x> $crate::Reborrow for $ident<'x, $crate::IsMut> {
+            type Ref<'g> = $ident<'g, $crate::IsRef> where Self: 'g;
+
+            #[inline]
+            fn reborrow(&self) -> Self::Ref<'_> {
+                $ident { bytes: self.bytes }
+            }
+        }
+
+        impl<'x> $crate::ReborrowMut for $ident<'x, $crate::IsMut> {
+            type Mut<'g> = $ident<'g, $crate::IsMut> where Self: 'g;
+
+            #[inline]
+            fn reborrow_mut(&mut self) -> Self::Mut<'_> {
+                $ident { bytes: self.bytes }
+            }
+        }
+    };
+}
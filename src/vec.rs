@@ -0,0 +1,99 @@
+use std::marker::PhantomData;
+
+use {DiceRef, DiceMut, Lense, LenseMut, LenseError, Plain, Le, Be};
+use aligned::{assert_fixed_size, max_of};
+use chunks::{Chunk, ChunkMut};
+
+/// An unsigned integer lense that can prefix a variable length field with its length
+pub trait Length: 'static + Plain + for<'a> Lense<Ref<'a> = &'a Self> {
+    /// The length in native byte order
+    fn to_len(&self) -> usize;
+}
+
+macro_rules! mk_length {
+    (prim $($ty:ty)*) => {$(
+        impl Length for $ty {
+            #[inline]
+            fn to_len(&self) -> usize {
+                *self as usize
+            }
+        }
+    )*};
+    (endian $($ty:ty)*) => {$(
+        impl Length for $ty {
+            #[inline]
+            fn to_len(&self) -> usize {
+                self.get() as usize
+            }
+        }
+    )*};
+}
+
+mk_length!{prim u8 u16 u32 u64}
+mk_length!{endian
+    Le<u8> Le<u16> Le<u32> Le<u64>
+    Be<u8> Be<u16> Be<u32> Be<u64>
+}
+
+/// A `Len` prefix followed by that many `L` items, lensed as a `Chunk` of the items
+///
+/// The length is read while dicing, so `SIZE` only counts the prefix; use `Lense::try_size_at`
+/// for the bytes a particular record occupies. The number of items is fixed once written, and
+/// the mutable lense can only change the items themselves.
+///
+/// ```
+/// # #[macro_use] extern crate lense;
+/// # use lense::{Lense, LenseVec, IsRef};
+/// mk_lense_struct!{pub struct Path: hops: LenseVec<u8, u8>, ttl: u8,}
+///
+/// # fn main() {
+/// let bytes = [3, 10, 20, 30, 64];
+/// let path = Path::<IsRef>::lense(&mut &bytes[..]);
+/// assert_eq!(path.hops.iter().copied().collect::<Vec<_>>(), [10, 20, 30]);
+/// assert_eq!(*path.ttl, 64);
+/// assert_eq!(Path::<IsRef>::try_size_at(&bytes), Ok(5));
+/// # }
+/// ```
+///
+/// Records holding one aren't `FIXED_SIZE`, so they can't be kept in a pool or slice.
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate lense;
+/// # use lense::{LenseVec, IsMut, SeekablePool};
+/// mk_lense_struct!{pub struct Path: hops: LenseVec<u8, u8>, ttl: u8,}
+///
+/// # fn main() {
+/// let pool = SeekablePool::<Path<IsMut>>::with_capacity(1);
+/// # }
+/// ```
+pub struct LenseVec<Len, L>(PhantomData<(Len, L)>);
+
+impl<Len: Length, L: Lense> Lense for LenseVec<Len, L> {
+    type Ref<'a> = Chunk<'a, L>;
+
+    const SIZE: usize = Len::SIZE;
+    const ALIGN: usize = max_of(&[Len::ALIGN, L::ALIGN]);
+    const FIXED_SIZE: bool = false;
+
+    #[inline]
+    fn try_lense<'a, Buf: DiceRef<'a>>(buf: &mut Buf) -> Result<Self::Ref<'a>, LenseError> {
+        assert_fixed_size::<L>();
+        let len = buf.try_dice::<Len>()?.to_len();
+        buf.pad_to(L::ALIGN)?;
+        let bytes = buf.try_dice_bytes(len.saturating_mul(L::SIZE))?;
+        Ok(Chunk::new(bytes, len, L::SIZE))
+    }
+}
+
+impl<Len: Length, L: LenseMut> LenseMut for LenseVec<Len, L> {
+    type Mut<'a> = ChunkMut<'a, L>;
+
+    #[inline]
+    fn try_lense_mut<'a, Buf: DiceMut<'a>>(buf: &mut Buf) -> Result<Self::Mut<'a>, LenseError> {
+        assert_fixed_size::<L>();
+        let len = buf.try_dice::<Len>()?.to_len();
+        buf.pad_to(L::ALIGN)?;
+        let bytes = buf.try_dice_bytes_mut(len.saturating_mul(L::SIZE))?;
+        Ok(ChunkMut::new(bytes, len, L::SIZE))
+    }
+}
@@ -15,7 +15,7 @@ type TupleAlice = (u8, (u8, u16), u32, u64);
 fn tuple_alice_iter() {
     let pool = SeekablePool::<TupleAlice>::with_capacity(1);
     for guard in pool.iter() {
-        let (a, (b, c), d, e) = *guard;
+        let (a, (b, c), d, e) = guard.lense();
         assert_eq!(*a, 0u8);
         assert_eq!(*b, 0u8);
         assert_eq!(*c, 0u16);
@@ -28,7 +28,7 @@ fn tuple_alice_iter() {
 fn alice_iter() {
     let pool = SeekablePool::<Alice<IsRef>>::with_capacity(4);
     for guard in pool.iter() {
-        let Alice { a, bc: (b, c), d, e } = *guard;
+        let Alice { a, bc: (b, c), d, e } = guard.lense();
         assert_eq!(*a, 0u8);
         assert_eq!(*b, 0u8);
         assert_eq!(*c, 0u16);
@@ -38,6 +38,28 @@ fn alice_iter() {
 }
 
 #[test]
+#[cfg(not(feature = "automatic_padding"))]
 fn size_alice_16() {
     assert_eq!(Alice::<::lense::IsRef>::size(), 16);
 }
+
+// Automatic padding places `bc` at 2 and `d` at 8: 1 + 1 + 4 + 2 + 4 + 4 + 8 = 24
+#[test]
+#[cfg(feature = "automatic_padding")]
+fn size_alice_24_padded() {
+    assert_eq!(Alice::<::lense::IsRef>::size(), 24);
+}
+
+// Sizes are known at compile time
+const _: () = assert!(Alice::<IsRef>::SIZE % Alice::<IsRef>::ALIGN == 0);
+const ALICE_X4: [u8; Alice::<IsRef>::SIZE * 4] = [0; Alice::<IsRef>::SIZE * 4];
+
+#[test]
+fn const_size_align() {
+    assert_eq!(ALICE_X4.len(), Alice::<IsRef>::size() * 4);
+    assert_eq!(Alice::<IsRef>::ALIGN, 8);
+    assert_eq!(<(u8, (u8, u16))>::ALIGN, 2);
+    assert_eq!(<[u32; 3]>::SIZE, 12);
+    assert_eq!(<[u32; 3]>::ALIGN, 4);
+    assert_eq!(<()>::ALIGN, 1);
+}
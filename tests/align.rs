@@ -0,0 +1,62 @@
+#[macro_use] extern crate lense;
+
+use lense::{Align16, Align64, Align4096, IsMut, SeekablePool};
+
+mk_lense_struct!{pub struct Point:
+    x: u32,
+    y: u32,
+    z: u32,
+}
+
+fn addr<T>(lense: &T) -> usize {
+    lense as *const T as usize
+}
+
+#[test]
+fn records_start_on_the_boundary() {
+    let pool = SeekablePool::<Point<IsMut>, Align16>::aligned(4);
+    assert_eq!(pool.as_bytes().lense().len(), 64);
+    for guard in pool.iter() {
+        assert_eq!(addr(guard.lense().x) % 16, 0);
+    }
+    let page = SeekablePool::<u8, Align4096>::aligned(2);
+    assert_eq!(addr(page.get(1).lense()) % 4096, 0);
+}
+
+#[test]
+fn stride_padding_is_untouched() {
+    let mut pool = SeekablePool::<Point<IsMut>, Align16>::default();
+    for i in 0..3 {
+        pool.push_with(|point| *point.z = i + 1);
+    }
+    assert_eq!(pool.len(), 3);
+    assert!(pool.capacity() >= 3);
+    assert_eq!(*pool.get(2).lense().z, 3);
+    let guard = pool.as_bytes();
+    let bytes = guard.lense();
+    assert_eq!(&bytes[16 + 8..16 + 12], &2u32.to_ne_bytes());
+    assert_eq!(&bytes[16 + 12..32], &[0; 4]);
+}
+
+#[test]
+fn chunks_and_splits_follow_the_stride() {
+    let mut pool = SeekablePool::<u32, Align64>::aligned(6);
+    pool.par_iter_mut(3, |pos, x| *x = pos as u32);
+    let sums: Vec<u32> = pool.chunks(4).map(|chunk| chunk.lense().iter().sum()).collect();
+    assert_eq!(sums, [6, 9]);
+    for mut sub in pool.chunks_exact_mut(3) {
+        let start = sub.start() as u32;
+        for x in sub.iter_mut() {
+            *x += start;
+        }
+    }
+    let values: Vec<u32> = pool.iter().map(|guard| *guard.lense()).collect();
+    assert_eq!(values, [0, 1, 2, 6, 7, 8]);
+}
+
+#[test]
+#[should_panic(expected = "Records are padded apart")]
+fn padded_chunks_are_not_slices() {
+    let pool = SeekablePool::<u32, Align16>::aligned(2);
+    pool.try_get_chunk(0..2).unwrap().lense().as_slice();
+}
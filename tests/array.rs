@@ -0,0 +1,72 @@
+#[macro_use] extern crate lense;
+
+use lense::{Block, Lense, LenseMut, LenseError, SeekablePool, IsRef, IsMut};
+
+mk_lense_struct!{pub struct Record:
+    id:    u32,
+    small: [u8; 60],
+    data:  Block<u8, 256>,
+    words: [u32; 64],
+}
+
+#[repr(align(8))]
+struct Aligned<T>(T);
+
+#[test]
+fn size_large_arrays() {
+    assert_eq!(<[u8; 64]>::size(), 64);
+    assert_eq!(<[u32; 256]>::size(), 1024);
+    assert_eq!(<[[u16; 40]; 3]>::size(), 240);
+    assert_eq!(Block::<u64, 100>::size(), 800);
+    assert_eq!(Record::<IsRef>::size(), 4 + 60 + 256 + 256);
+}
+
+#[test]
+fn lense_u32_256() {
+    let mut buf = Aligned([0u8; 1024]);
+    {
+        let mut words = <[u32; 256]>::lense_mut(&mut &mut buf.0[..]);
+        for (i, w) in words.iter_mut().enumerate() {
+            **w = i as u32;
+        }
+    }
+    let words = <[u32; 256]>::lense(&mut &buf.0[..]);
+    assert_eq!(*words[0], 0);
+    assert_eq!(*words[255], 255);
+}
+
+#[test]
+fn block_is_a_single_view() {
+    let mut buf = Aligned([0u8; 64]);
+    {
+        let block: &mut [u16; 32] = Block::<u16, 32>::lense_mut(&mut &mut buf.0[..]);
+        block[31] = 0xBEEF;
+    }
+    let block: &[u16; 32] = Block::<u16, 32>::lense(&mut &buf.0[..]);
+    assert_eq!(block[31], 0xBEEF);
+    assert!(block[..31].iter().all(|&x| x == 0));
+}
+
+#[test]
+fn truncated_large_arrays() {
+    let buf = Aligned([0u8; 63]);
+    assert_eq!(<[u8; 64]>::try_lense(&mut &buf.0[..]).err(),
+               Some(LenseError::Truncated { needed: 1, available: 0 }));
+    assert_eq!(Block::<u8, 64>::try_lense(&mut &buf.0[..]).err(),
+               Some(LenseError::Truncated { needed: 64, available: 63 }));
+}
+
+#[test]
+fn pool_of_records() {
+    let mut pool = SeekablePool::<Record<IsMut>>::with_capacity(2);
+    for mut guard in pool.iter_mut() {
+        let record = guard.lense_mut();
+        record.data[255] = 7;
+        *record.words[63] = 9;
+    }
+    for guard in pool.iter() {
+        let record = guard.lense();
+        assert_eq!(record.data[255], 7);
+        assert_eq!(*record.words[63], 9);
+    }
+}
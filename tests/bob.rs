@@ -1,7 +1,7 @@
 #![cfg(feature = "automatic_padding")]
 #[macro_use] extern crate lense;
 
-use lense::{Lense, IsRef};
+use lense::{Lense, LenseError, SeekablePool, Aligned, IsRef, IsMut};
 
 // Bad padding leading to 8 wasted bytes
 mk_lense_struct!{struct Bob:
@@ -16,7 +16,58 @@ mk_lense_struct!{struct Bob:
 } // 1 + 1 + 2 + 1 + 1 + 4 + 8 + 6 = 24
 
 #[test]
-#[ignore]
 fn size_bob_24_padded() {
     assert_eq!(Bob::<IsRef>::size(), 24);
+    assert_eq!(Bob::<IsRef>::ALIGN, 8);
+}
+
+#[test]
+fn bob_padded_offsets() {
+    let mut pool = SeekablePool::<Bob<IsMut>>::with_capacity(2);
+    for mut guard in pool.iter_mut() {
+        let bob = guard.lense_mut();
+        *bob._a = 1;
+        *bob._bc.0 = 2;
+        *bob._bc.1 = 3;
+        *bob._d = 4;
+        *bob._e = 5;
+    }
+
+    let mut record = [0u8; 24];
+    record[0] = 1;
+    record[2] = 2;
+    record[4] = 3;
+    record[8] = 4;
+    record[16] = 5;
+    let bytes = pool.as_bytes();
+    assert_eq!(&bytes.lense()[..24], &record[..]);
+    assert_eq!(&bytes.lense()[24..], &record[..]);
+}
+
+// Tuples and arrays of composites carry their trailing padding
+#[test]
+fn nested_padding() {
+    assert_eq!(<(u8, u32)>::size(), 8);
+    assert_eq!(<[(u32, u8); 3]>::size(), 24);
+    assert_eq!(<(u8, (u8, u64))>::size(), 24);
+    assert_eq!(<(u16, [u8; 3], u16)>::size(), 8);
+}
+
+#[test]
+fn padding_is_bounds_checked() {
+    let pool = SeekablePool::<u64>::with_capacity(3);
+    let bytes = pool.as_bytes();
+    let bytes = bytes.lense();
+
+    // `_d` ends at 12 and the 4 bytes of padding before `_e` run past the buffer
+    let ptr = &mut Aligned::new(&bytes[..14]);
+    assert_eq!(Bob::<IsRef>::try_lense(ptr).err(),
+               Some(LenseError::Truncated { needed: 4, available: 2 }));
+
+    // `_e` starts at 16, leaving 4 of its 8 bytes
+    let ptr = &mut Aligned::new(&bytes[..20]);
+    assert_eq!(Bob::<IsRef>::try_lense(ptr).err(),
+               Some(LenseError::Truncated { needed: 8, available: 4 }));
+
+    assert!(Bob::<IsRef>::try_lense(&mut Aligned::new(bytes)).is_ok());
 }
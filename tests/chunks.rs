@@ -0,0 +1,90 @@
+#[macro_use] extern crate lense;
+
+use lense::{LockingIter, PoolError, SeekablePool, IsMut};
+
+mk_lense_struct!{pub struct Point:
+    x: u32,
+    y: u32,
+}
+
+#[test]
+fn chunks_cover_the_pool() {
+    let mut pool = SeekablePool::<u32>::with_capacity(7);
+    for (pos, mut chunk) in pool.chunks_mut(3).indexed() {
+        for (i, x) in chunk.lense_mut().as_mut_slice().iter_mut().enumerate() {
+            *x = (pos + i) as u32;
+        }
+    }
+    let lens: Vec<usize> = pool.chunks(3).map(|chunk| chunk.lense().len()).collect();
+    assert_eq!(lens, [3, 3, 1]);
+    assert_eq!(pool.chunks(3).size_hint(), (0, Some(3)));
+    let sums: Vec<u32> = pool.chunks(3)
+        .map(|chunk| chunk.lense().as_slice().iter().sum())
+        .collect();
+    assert_eq!(sums, [3, 12, 6]);
+}
+
+#[test]
+fn huge_chunks() {
+    let mut pool = SeekablePool::<u8>::with_capacity(3);
+    assert_eq!(pool.chunks(usize::MAX).count(), 1);
+    assert_eq!(pool.chunks_mut(usize::MAX).map(|chunk| chunk.lense().len()).sum::<usize>(), 3);
+    assert_eq!(pool.windows(usize::MAX).count(), 0);
+}
+
+#[test]
+fn chunk_of_structs() {
+    let mut pool = SeekablePool::<Point<IsMut>>::with_capacity(4);
+    for mut guard in pool.chunks_mut(2) {
+        let mut chunk = guard.lense_mut();
+        for point in chunk.iter_mut() {
+            *point.x = 1;
+        }
+        *chunk.get_mut(1).unwrap().y = 2;
+        assert!(chunk.get_mut(2).is_none());
+    }
+    let guard = pool.try_get_chunk(0..4).unwrap();
+    let chunk = guard.lense();
+    let ys: Vec<u32> = chunk.iter().map(|point| *point.y).collect();
+    assert_eq!(ys, [0, 2, 0, 2]);
+    assert_eq!(*chunk.get(2).unwrap().x, 1);
+    assert_eq!(chunk.as_bytes().len(), 32);
+}
+
+#[test]
+fn chunk_locks_as_a_group() {
+    let pool = SeekablePool::<u32>::with_capacity(6);
+    let guard = pool.get_mut(4);
+    assert_eq!(pool.try_get_chunk(2..6).err(), Some(PoolError::Locked(4)));
+    // The failed chunk released the records it had already locked
+    assert!(pool.try_get_mut(2).is_ok());
+    let chunk = pool.try_get_chunk_mut(0..4).unwrap();
+    assert_eq!(pool.try_get(3).err(), Some(PoolError::Locked(3)));
+    assert_eq!(pool.try_get_chunk(5..7).err(), Some(PoolError::OutOfBounds { pos: 6, len: 6 }));
+    drop((guard, chunk));
+    assert!(pool.try_get_chunk_mut(0..6).is_ok());
+}
+
+#[test]
+fn windows_slide() {
+    let mut pool = SeekablePool::<u32>::with_capacity(5);
+    for (pos, mut guard) in pool.iter_mut().indexed() {
+        *guard.lense_mut() = pos as u32;
+    }
+    let windows: Vec<Vec<u32>> = pool.windows(3).map(|w| w.lense().as_slice().to_vec()).collect();
+    assert_eq!(windows, [[0, 1, 2], [1, 2, 3], [2, 3, 4]]);
+    assert_eq!(pool.windows(3).size_hint(), (0, Some(3)));
+    assert_eq!(pool.windows(6).count(), 0);
+    // Overlapping windows share their read locks
+    let held: Vec<_> = pool.windows(2).collect();
+    assert_eq!(held.len(), 4);
+    assert_eq!(pool.try_get_mut(2).err(), Some(PoolError::Locked(2)));
+}
+
+#[test]
+fn chunks_report_locked() {
+    let pool = SeekablePool::<u32>::with_capacity(6);
+    let _guard = pool.get_mut(3);
+    let report: Vec<_> = pool.chunks(2).report_locked().map(|c| c.err()).collect();
+    assert_eq!(report, [None, Some(PoolError::Locked(3)), None]);
+}
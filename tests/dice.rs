@@ -0,0 +1,54 @@
+#[macro_use] extern crate lense;
+
+use lense::{Lense, LenseMut, LenseError, DiceRef, IsRef, IsMut};
+
+mk_lense_struct!{pub struct Alice:
+    a:  u8,        // 1
+    bc: (u8, u16), // 3
+    d:  u32,       // 4
+    e:  u64,       // 8
+} // 1 + 3 + 4 + 8 = 16
+
+// Dicing reinterprets the bytes in place, so keep the test buffers aligned
+#[repr(align(8))]
+struct Aligned<T>(T);
+
+#[test]
+fn try_dice_truncated() {
+    let buf = Aligned([0u8; 3]);
+    let mut ptr = &buf.0[..];
+    assert_eq!(ptr.try_dice::<u32>().err(),
+               Some(LenseError::Truncated { needed: 4, available: 3 }));
+    // A failed dice leaves the buffer untouched
+    assert_eq!(ptr.len(), 3);
+    assert!(ptr.try_dice::<u16>().is_ok());
+    assert_eq!(ptr.len(), 1);
+}
+
+#[test]
+fn try_lense_struct_truncated() {
+    let buf = Aligned([0u8; 15]);
+    let err = Alice::<IsRef>::try_lense(&mut &buf.0[..]).err();
+    // a, b, c and d fit; e needs 8 bytes with only 7 left
+    assert_eq!(err, Some(LenseError::Truncated { needed: 8, available: 7 }));
+}
+
+#[test]
+fn try_lense_mut_tuple_and_array() {
+    let mut buf = Aligned([0u8; 16]);
+    {
+        let (a, [b, c]) = <(u64, [u32; 2])>::try_lense_mut(&mut &mut buf.0[..]).unwrap();
+        *a = 1;
+        *b = 2;
+        *c = 3;
+    }
+    assert!(<(u64, [u32; 3])>::try_lense_mut(&mut &mut buf.0[..]).is_err());
+    assert!(Alice::<IsMut>::try_lense_mut(&mut &mut buf.0[..]).is_ok());
+}
+
+#[test]
+#[should_panic(expected = "needed 8 bytes, 0 available")]
+fn lense_truncated_panics() {
+    let buf = Aligned([0u8; 8]);
+    <[u64; 2]>::lense(&mut &buf.0[..]);
+}
@@ -0,0 +1,58 @@
+#[macro_use] extern crate lense;
+
+use lense::{Le, Be, SeekablePool, IsMut};
+
+mk_lense_struct!{pub struct Header:
+    version: u8,
+    kind:    u8,
+    flags:   Be<u16>,
+    length:  Be<u32>,
+    offset:  Le<u64>,
+}
+
+#[test]
+fn byte_order() {
+    assert_eq!(Be::<u16>::new(0x0102).get(), 0x0102);
+    assert_eq!(Le::<f64>::new(1.5).get(), 1.5);
+
+    let mut pool = SeekablePool::<Header<IsMut>>::with_capacity(1);
+    for mut guard in pool.iter_mut() {
+        let header = guard.lense_mut();
+        *header.version = 1;
+        *header.kind = 0;
+        header.flags.set(0x0102);
+        header.length.set(0x0304_0506);
+        header.offset.set(0x0708);
+    }
+
+    assert_eq!(pool.as_bytes().lense(), &[1, 0, 1, 2, 3, 4, 5, 6, 8, 7, 0, 0, 0, 0, 0, 0]);
+
+    for guard in pool.iter() {
+        let header = guard.lense();
+        assert_eq!(header.flags.get(), 0x0102);
+        assert_eq!(header.length.get(), 0x0304_0506);
+        assert_eq!(header.offset.get(), 0x0708);
+    }
+}
+
+#[test]
+fn array_of_be() {
+    let mut pool = SeekablePool::<[Be<u32>; 2]>::with_capacity(1);
+    for mut guard in pool.iter_mut() {
+        guard.lense_mut()[1].set(0xdead_beef);
+    }
+    assert_eq!(&pool.as_bytes().lense()[4..], &[0xde, 0xad, 0xbe, 0xef]);
+}
+
+#[test]
+fn floats_keep_their_bits() {
+    // A signalling NaN must come back with its payload untouched
+    let nan = f32::from_bits(0x7fa0_0001);
+    assert_eq!(Be::<f32>::new(nan).get().to_bits(), 0x7fa0_0001);
+    assert_eq!(Le::<f64>::new(-0.0).get().to_bits(), (-0.0f64).to_bits());
+
+    let pool = SeekablePool::<Be<f32>>::with_capacity(1);
+    pool.get_mut(0).lense_mut().set(nan);
+    assert_eq!(pool.as_bytes().lense(), &[0x7f, 0xa0, 0x00, 0x01]);
+    assert_eq!(pool.get(0).lense().get().to_bits(), 0x7fa0_0001);
+}
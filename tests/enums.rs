@@ -0,0 +1,151 @@
+#[macro_use] extern crate lense;
+
+use lense::{Aligned, Be, IsMut, IsRef, Lense, LenseError, LenseMut, PoolError, SeekablePool};
+
+mk_lense_struct!{pub enum Shape:
+    Dot => write_dot,
+    Circle(u32) => write_circle,
+    Rect(u16, u16, u32) => write_rect,
+    Label([u8; 3]) => write_label,
+}
+
+#[test]
+fn size_includes_tag_and_padding() {
+    let padded = cfg!(feature = "automatic_padding");
+    assert_eq!(Shape::<IsRef>::ALIGN, 4);
+    // Tag, padding up to the payload's alignment, then the largest payload
+    assert_eq!(Shape::<IsRef>::SIZE, if padded { 4 + 8 } else { 1 + 8 });
+}
+
+// Without padding the payload starts straight after the tag
+mk_lense_struct!{pub enum Op:
+    Nop,
+    Move(i8, i8) => write_move,
+    Say([u8; 2]) => write_say,
+}
+
+#[test]
+fn byte_payloads() {
+    assert_eq!(Op::<IsRef>::SIZE, 3);
+    let pool = SeekablePool::<Op<IsMut>>::with_capacity(2);
+    if let Op::Move(x, y) = Op::write_move(&mut pool.get_mut(0)) {
+        *x = -1;
+        *y = 1;
+    }
+    if let Op::Say(word) = Op::write_say(&mut pool.get_mut(1)) {
+        *word[0] = b'h';
+        *word[1] = b'i';
+    }
+    let bytes = pool.as_bytes().lense().to_vec();
+    assert_eq!(bytes, [1, 0xff, 1, 2, b'h', b'i']);
+    let mut buf = &bytes[..];
+    assert!(matches!(Op::<IsRef>::lense(&mut buf), Op::Move(&-1, &1)));
+    assert!(matches!(Op::<IsRef>::lense(&mut buf), Op::Say([&b'h', &b'i'])));
+}
+
+#[test]
+#[cfg(feature = "automatic_padding")]
+fn pool_of_enums_keeps_to_its_slots() {
+    let mut pool = SeekablePool::<Shape<IsMut>>::with_capacity(3);
+    for (pos, mut guard) in pool.iter_mut().enumerate() {
+        match Shape::write_rect(&mut guard) {
+            Shape::Rect(w, h, area) => {
+                *w = pos as u16;
+                *h = 2;
+                *area = u32::MAX;
+            }
+            _ => unreachable!(),
+        }
+    }
+    for (pos, guard) in pool.iter().enumerate() {
+        match guard.lense() {
+            Shape::Rect(w, h, area) => assert_eq!((*w, *h, *area), (pos as u16, 2, u32::MAX)),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "automatic_padding")]
+fn writers_zero_the_old_payload() {
+    let pool = SeekablePool::<Shape<IsMut>>::with_capacity(1);
+    let mut guard = pool.get_mut(0);
+    if let Shape::Label(label) = Shape::write_label(&mut guard) {
+        *label[0] = b'x';
+    }
+    match Shape::write_circle(&mut guard) {
+        Shape::Circle(r) => assert_eq!(*r, 0),
+        _ => unreachable!(),
+    }
+    assert!(matches!(guard.lense(), Shape::Circle(_)));
+    assert!(matches!(Shape::write_dot(&mut guard), Shape::Dot));
+    drop(guard);
+    assert!(matches!(pool.get(0).lense(), Shape::Dot));
+}
+
+#[test]
+fn unknown_tag_is_invalid() {
+    let pool = SeekablePool::<Shape<IsMut>>::with_capacity(1);
+    let mut bytes = pool.as_bytes().lense().to_vec();
+    bytes[0] = 9;
+    assert!(matches!(Shape::<IsRef>::lense(&mut Aligned::new(&bytes[..])), Shape::InvalidLense));
+    assert_eq!(Shape::<IsRef>::try_lense(&mut Aligned::new(&bytes[..]))
+                   .err(), Some(LenseError::UnknownTag { tag: 9 }));
+    assert!(matches!(Shape::<IsRef>::try_lense(&mut Aligned::new(&bytes[..1])),
+                     Err(LenseError::Truncated { .. })));
+}
+
+// Neither enum would otherwise use its lifetime and mode
+mk_lense_struct!{pub enum Kind(u16):
+    A = 1,
+    B = 2,
+}
+
+mk_lense_struct!{pub enum Only:
+    Value(u8),
+}
+
+#[test]
+fn fieldless_and_single_variant_enums() {
+    assert_eq!(Kind::<IsRef>::SIZE, 2);
+    let mut pool = SeekablePool::<Kind<IsMut>>::with_capacity(1);
+    pool.as_bytes_mut().copy_from_slice(&2u16.to_ne_bytes());
+    assert!(matches!(pool.get(0).lense(), Kind::B));
+    let bytes = [0, 7];
+    assert!(matches!(Only::<IsRef>::lense(&mut &bytes[..]), Only::Value(&7)));
+}
+
+mk_lense_struct!{pub enum Ether(Be<u16>):
+    Ipv4([u8; 4]) = 0x0800,
+    Arp = 0x0806,
+    Rarp => write_rarp,
+    Vlan(Be<u16>) = 0x8100 => write_vlan,
+}
+
+#[test]
+fn explicit_discriminants_and_tag_type() {
+    assert_eq!(Ether::<IsRef>::SIZE, 6);
+    let mut pool = SeekablePool::<Ether<IsMut>>::with_capacity(1);
+    {
+        let mut guard = pool.get_mut(0);
+        assert!(matches!(Ether::write_rarp(&mut guard), Ether::Rarp));
+        assert_eq!(pool.try_get(0).err(), Some(PoolError::Locked(0)));
+    }
+    assert_eq!(pool.as_bytes().lense()[..2], [0x08, 0x07]);
+    assert!(matches!(pool.get(0).lense(), Ether::Rarp));
+    if let Ether::Vlan(id) = Ether::write_vlan(&mut pool.get_mut(0)) {
+        id.set(42);
+    }
+    assert_eq!(pool.as_bytes().lense(), [0x81, 0x00, 0, 42, 0, 0]);
+    assert!(matches!(Ether::<IsMut>::lense_mut(&mut pool.as_bytes_mut()), Ether::Vlan(_)));
+}
+
+#[test]
+fn unknown_discriminants_are_errors() {
+    let mut pool = SeekablePool::<Ether<IsMut>>::with_capacity(1);
+    assert_eq!(Ether::<IsRef>::try_lense(&mut pool.as_bytes().lense()).err(),
+               Some(LenseError::UnknownTag { tag: 0 }));
+    pool.as_bytes_mut()[..2].copy_from_slice(&[0x08, 0x01]);
+    assert!(matches!(pool.get(0).lense(), Ether::InvalidLense));
+    assert!(matches!(Ether::<IsMut>::lense_mut(&mut pool.as_bytes_mut()), Ether::InvalidLense));
+}
@@ -0,0 +1,85 @@
+#[macro_use] extern crate lense;
+
+use lense::{SeekablePool, PoolError, IsMut};
+
+mk_lense_struct!{pub struct Record:
+    id:    u32,
+    value: u32,
+}
+
+#[test]
+fn get_mut_then_get() {
+    let pool = SeekablePool::<Record<IsMut>>::with_capacity(8);
+    {
+        let mut guard = pool.get_mut(5);
+        let record = guard.lense_mut();
+        *record.id = 5;
+        *record.value = 50;
+    }
+    let guard = pool.get(5);
+    let record = guard.lense();
+    assert_eq!((*record.id, *record.value), (5, 50));
+    assert_eq!(*pool.get(4).lense().id, 0);
+}
+
+#[test]
+fn many_records_at_once() {
+    let pool = SeekablePool::<u64>::with_capacity(4);
+    let mut a = pool.get_mut(0);
+    let mut b = pool.get_mut(3);
+    *a.lense_mut() = 1;
+    *b.lense_mut() = 2;
+    assert_eq!(pool.try_get(3).err(), Some(PoolError::Locked(3)));
+    drop((a, b));
+    assert_eq!((*pool.get(0).lense(), *pool.get(3).lense()), (1, 2));
+}
+
+#[test]
+fn out_of_bounds() {
+    let pool = SeekablePool::<u64>::with_capacity(4);
+    assert_eq!(pool.try_get(4).err(), Some(PoolError::OutOfBounds { pos: 4, len: 4 }));
+    assert_eq!(pool.try_get_mut(9).err(), Some(PoolError::OutOfBounds { pos: 9, len: 4 }));
+}
+
+#[test]
+#[should_panic(expected = "Invalid index! 4 is out of bounds for a pool of 4")]
+fn get_out_of_bounds_panics() {
+    let pool = SeekablePool::<u64>::with_capacity(4);
+    pool.get(4);
+}
+
+#[test]
+fn range_locks_all_or_nothing() {
+    let pool = SeekablePool::<u64>::with_capacity(4);
+    {
+        let guards = pool.try_get_range_mut(1..3).unwrap();
+        assert_eq!(guards.len(), 2);
+        assert_eq!(pool.try_get_range(0..4).err(), Some(PoolError::Locked(1)));
+        // The failed range released the slots it had already taken
+        assert!(pool.try_get(0).is_ok());
+    }
+    assert_eq!(pool.try_get_range(2..5).err(), Some(PoolError::OutOfBounds { pos: 4, len: 4 }));
+    assert_eq!(pool.try_get_range(0..4).unwrap().len(), 4);
+}
+
+#[test]
+#[should_panic(expected = "Pool has outstanding mutable guards")]
+fn bytes_while_lensed() {
+    let pool = SeekablePool::<u64>::with_capacity(1);
+    let _guard = pool.get_mut(0);
+    pool.as_bytes();
+}
+
+#[test]
+fn shared_readers() {
+    let pool = SeekablePool::<u64>::with_capacity(3);
+    let first: Vec<_> = pool.iter().collect();
+    let second: Vec<_> = pool.iter().collect();
+    assert_eq!((first.len(), second.len()), (3, 3));
+    assert_eq!(pool.try_get_mut(1).err(), Some(PoolError::Locked(1)));
+    // Readers don't block the byte view
+    assert_eq!(pool.len(), 3);
+    assert_eq!(pool.as_bytes().lense().len(), 24);
+    drop((first, second));
+    assert!(pool.try_get_mut(1).is_ok());
+}
@@ -0,0 +1,121 @@
+#[macro_use] extern crate lense;
+
+use lense::{SeekablePool, PoolError, IsMut};
+
+mk_lense_struct!{pub struct Record:
+    id:    u32,
+    value: u16,
+    flags: u16,
+}
+
+#[test]
+fn push_and_get() {
+    let mut pool = SeekablePool::<Record<IsMut>>::new();
+    assert_eq!(pool.try_get(0).err(), Some(PoolError::OutOfBounds { pos: 0, len: 0 }));
+    assert_eq!(pool.push_zeroed(), 0);
+    let pos = pool.push_with(|record| {
+        *record.id = 7;
+        *record.value = 70;
+    });
+    assert_eq!(pos, 1);
+    assert_eq!(*pool.get(0).lense().id, 0);
+    let guard = pool.get(1);
+    let record = guard.lense();
+    assert_eq!((*record.id, *record.value), (7, 70));
+    drop(guard);
+    assert_eq!(pool.iter().count(), 2);
+}
+
+#[test]
+fn pop_and_truncate() {
+    let mut pool = SeekablePool::<u16>::with_capacity(5);
+    assert!(pool.pop());
+    assert_eq!(pool.iter().count(), 4);
+    pool.truncate(8);
+    assert_eq!(pool.iter().count(), 4);
+    pool.truncate(1);
+    assert_eq!(pool.try_get(1).err(), Some(PoolError::OutOfBounds { pos: 1, len: 1 }));
+    assert!(pool.pop());
+    assert!(!pool.pop());
+}
+
+#[test]
+fn regrown_records_are_zeroed() {
+    // Three u16 records share one backing word, so truncating leaves bytes behind
+    let mut pool = SeekablePool::<u16>::with_capacity(3);
+    for mut guard in pool.iter_mut() {
+        *guard.lense_mut() = 0xffff;
+    }
+    pool.truncate(1);
+    pool.resize(3);
+    assert_eq!(pool.as_bytes().lense(), &[0xff, 0xff, 0, 0, 0, 0]);
+    pool.push_with(|x| *x = 1);
+    assert_eq!(*pool.get(3).lense(), 1);
+}
+
+#[test]
+fn reserve_keeps_records() {
+    let mut pool = SeekablePool::<u64>::with_capacity(2);
+    *pool.get_mut(1).lense_mut() = 42;
+    pool.reserve(100);
+    assert_eq!(pool.iter().count(), 2);
+    assert_eq!(*pool.get(1).lense(), 42);
+    assert_eq!(pool.as_bytes().lense().len(), 16);
+}
+
+#[test]
+fn file_grows_to_fit() {
+    let mut file = ::std::fs::File::open("lense-testing-file.dat").unwrap();
+    let mut lf = lense::LenseFile::<u64>::new();
+    assert_eq!(lf.read_file(&mut file).unwrap(), 80);
+    assert_eq!(lf.iter().count(), 10);
+    assert_eq!(*lf.get(9).lense(), u64::from_ne_bytes([8, 9, 10, 11, 12, 13, 14, 15]));
+}
+
+#[test]
+fn file_rounds_up_to_whole_records() {
+    let mut file = ::std::fs::File::open("lense-testing-file.dat").unwrap();
+    let mut lf = lense::LenseFile::<[u8; 3]>::new();
+    assert_eq!(lf.read_file(&mut file).unwrap(), 80);
+    assert_eq!(lf.iter().count(), 27);
+    // The last record is zero padded past the end of the file
+    let last = lf.get(26);
+    assert_eq!([*last.lense()[0], *last.lense()[1], *last.lense()[2]], [14, 15, 0]);
+}
+
+#[test]
+fn file_of_zero_sized_records() {
+    let mut file = ::std::fs::File::open("lense-testing-file.dat").unwrap();
+    let mut lf = lense::LenseFile::<()>::new();
+    let err = lf.read_file(&mut file).unwrap_err();
+    assert_eq!(err.kind(), ::std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn len_and_capacity() {
+    let mut pool = SeekablePool::<u32>::new();
+    assert!(pool.is_empty());
+    pool.reserve(10);
+    assert!(pool.capacity() >= 10);
+    assert_eq!(pool.len(), 0);
+    assert_eq!(pool.as_bytes().lense().len(), 0);
+    pool.resize(3);
+    assert_eq!(pool.len(), 3);
+    assert!(!pool.is_empty());
+    assert_eq!(pool.as_bytes().lense().len(), 12);
+    pool.as_bytes_mut()[4] = 1;
+    assert_eq!(*pool.get(1).lense(), u32::from_ne_bytes([1, 0, 0, 0]));
+}
+
+#[test]
+fn iterator_bound_matches_iteration() {
+    let mut pool = SeekablePool::<u16>::with_capacity(2);
+    pool.reserve(30);
+    pool.push_zeroed();
+    let mut it = pool.iter();
+    assert_eq!(it.size_hint(), (0, Some(3)));
+    it.next();
+    assert_eq!(it.size_hint(), (0, Some(2)));
+    assert_eq!(it.count(), 2);
+    assert_eq!(pool.iter_mut().count(), 3);
+}
@@ -0,0 +1,106 @@
+#[macro_use] extern crate lense;
+
+use lense::{Guard, PoolError, SeekablePool, IsMut};
+
+mk_lense_struct!{pub struct Alice:
+    a: u32,
+    b: u32,
+    d: u64,
+}
+
+fn field_d<'a>(pool: &'a SeekablePool<Alice<IsMut>>, pos: usize) -> Guard<'a, &'a mut u64> {
+    Guard::map(pool.get_mut(pos), |alice| alice.d)
+}
+
+#[test]
+fn map_keeps_the_lock() {
+    let pool = SeekablePool::<Alice<IsMut>>::with_capacity(8);
+    {
+        let mut d = field_d(&pool, 7);
+        *d.lense_mut() = 70;
+        assert_eq!(pool.try_get(7).err(), Some(PoolError::Locked(7)));
+    }
+    assert_eq!(*pool.get(7).lense().d, 70);
+    assert!(pool.try_get_mut(7).is_ok());
+}
+
+#[test]
+fn try_map() {
+    let pool = SeekablePool::<Alice<IsMut>>::with_capacity(1);
+    let guard = Guard::try_map(pool.get(0), |alice| {
+        if *alice.a > 0 { Ok(alice.b) } else { Err(alice) }
+    });
+    let guard = guard.err().expect("a is zero");
+    assert_eq!(pool.try_get_mut(0).err(), Some(PoolError::Locked(0)));
+    let b = Guard::try_map(guard, |alice| Ok::<_, Alice<_>>(alice.b)).ok().unwrap();
+    assert_eq!(*b.lense(), 0);
+    drop(b);
+    assert!(pool.try_get_mut(0).is_ok());
+}
+
+#[test]
+fn split_into_fields() {
+    let pool = SeekablePool::<Alice<IsMut>>::with_capacity(2);
+    let (mut a, rest) = Guard::split(pool.get_mut(1), |alice| (alice.a, (alice.b, alice.d)));
+    let (mut b, mut d) = Guard::split(rest, |fields| fields);
+    // Each field can be updated on its own code path
+    *a.lense_mut() = 1;
+    *b.lense_mut() = 2;
+    *d.lense_mut() = 3;
+    drop(a);
+    drop(d);
+    assert_eq!(pool.try_get(1).err(), Some(PoolError::Locked(1)));
+    drop(b);
+    let guard = pool.get(1);
+    let alice = guard.lense();
+    assert_eq!((*alice.a, *alice.b, *alice.d), (1, 2, 3));
+}
+
+#[test]
+fn split_shared() {
+    let pool = SeekablePool::<Alice<IsMut>>::with_capacity(1);
+    let (a, b) = Guard::split(pool.get(0), |alice| (alice.a, alice.b));
+    let other = pool.get(0);
+    drop((a, other));
+    assert_eq!(pool.try_get_mut(0).err(), Some(PoolError::Locked(0)));
+    drop(b);
+    assert!(pool.try_get_mut(0).is_ok());
+}
+
+#[test]
+fn map_releases_on_panic() {
+    let pool = SeekablePool::<Alice<IsMut>>::with_capacity(1);
+    let result = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| {
+        Guard::map(pool.get_mut(0), |_| -> () { panic!("boom") })
+    }));
+    assert!(result.is_err());
+    assert!(pool.try_get_mut(0).is_ok());
+}
+
+#[test]
+fn upgrade_and_downgrade() {
+    let pool = SeekablePool::<Alice<IsMut>>::with_capacity(2);
+    let reader = pool.get(1);
+    let mut writer = reader.upgrade().ok().expect("sole reader");
+    *writer.lense_mut().b = 5;
+    assert_eq!(pool.try_get(1).err(), Some(PoolError::Locked(1)));
+    let reader = writer.downgrade();
+    assert_eq!(*reader.lense().b, 5);
+    // Readers may join once downgraded, which blocks another upgrade
+    let other = pool.get(1);
+    let reader = reader.upgrade().err().expect("shared with another reader");
+    drop(other);
+    assert!(reader.upgrade().is_ok());
+    assert!(pool.try_get_mut(1).is_ok());
+}
+
+#[test]
+fn upgrade_prim_from_iter() {
+    let pool = SeekablePool::<u32>::with_capacity(3);
+    for guard in pool.iter() {
+        if let Ok(mut guard) = guard.upgrade() {
+            *guard.lense_mut() += 1;
+        }
+    }
+    assert_eq!(pool.iter().map(|x| *x.lense()).sum::<u32>(), 3);
+}
@@ -1,12 +1,12 @@
 extern crate lense;
 
-use lense::SeekablePool;
+use lense::{LockingIter, PoolError, SeekablePool, SyncPool};
 
 #[test]
 fn prim_immutable_iter() {
     let pool = SeekablePool::<u16>::with_capacity(1);
     for guard in pool.iter() {
-        assert_eq!(**guard, 0u16);
+        assert_eq!(*guard.lense(), 0u16);
     }
 }
 
@@ -14,6 +14,122 @@ fn prim_immutable_iter() {
 fn prim_mutable_iter() {
     let mut pool = SeekablePool::<u16>::with_capacity(1);
     for mut guard in pool.iter_mut() {
-        **guard = 12345;
+        *guard.lense_mut() = 12345;
     }
 }
+
+#[test]
+fn iter_ends_at_locked_slot() {
+    let pool = SeekablePool::<u16>::with_capacity(4);
+    let _guard = pool.get_mut(1);
+    assert_eq!(pool.iter().size_hint(), (0, Some(4)));
+    assert_eq!(pool.iter().count(), 1);
+}
+
+#[test]
+fn skip_locked() {
+    let pool = SeekablePool::<u16>::with_capacity(4);
+    let _a = pool.get_mut(0);
+    let _b = pool.get_mut(2);
+    assert_eq!(pool.iter().skip_locked().count(), 2);
+    let slots: Vec<usize> = pool.iter().skip_locked().indexed().map(|(pos, _)| pos).collect();
+    assert_eq!(slots, [1, 3]);
+}
+
+#[test]
+fn report_locked() {
+    let pool = SeekablePool::<u16>::with_capacity(3);
+    let _guard = pool.get_mut(1);
+    let report: Vec<_> = pool.iter().report_locked().map(|r| r.err()).collect();
+    assert_eq!(report, [None, Some(PoolError::Locked(1)), None]);
+    for (pos, slot) in pool.iter().report_locked().indexed() {
+        assert_eq!(slot.is_ok(), pos != 1);
+    }
+}
+
+#[test]
+fn indexed_mut() {
+    let mut pool = SeekablePool::<u16>::with_capacity(3);
+    for (pos, mut guard) in pool.iter_mut().indexed() {
+        *guard.lense_mut() = pos as u16 * 10;
+    }
+    assert_eq!(*pool.get(2).lense(), 20);
+}
+
+#[test]
+fn sync_iter_waits() {
+    let pool = SyncPool::<u32>::with_capacity(4);
+    let guard = pool.get_mut(2);
+    ::std::thread::scope(|s| {
+        let reader = s.spawn(|| pool.iter().indexed().map(|(pos, _)| pos).collect::<Vec<_>>());
+        assert_eq!(pool.iter().skip_locked().count(), 3);
+        drop(guard);
+        assert_eq!(reader.join().unwrap(), [0, 1, 2, 3]);
+    });
+    for mut guard in pool.iter_mut() {
+        *guard.lense_mut() += 1;
+    }
+    assert_eq!(pool.iter().map(|guard| *guard.lense()).sum::<u32>(), 4);
+}
+
+#[test]
+fn double_ended() {
+    let mut pool = SeekablePool::<u16>::with_capacity(4);
+    for (pos, mut guard) in pool.iter_mut().indexed() {
+        *guard.lense_mut() = pos as u16;
+    }
+    let back: Vec<u16> = pool.iter().rev().map(|guard| *guard.lense()).collect();
+    assert_eq!(back, [3, 2, 1, 0]);
+    let mut it = pool.iter();
+    assert_eq!(it.next().map(|g| *g.lense()), Some(0));
+    assert_eq!(it.next_back().map(|g| *g.lense()), Some(3));
+    assert_eq!(it.size_hint(), (0, Some(2)));
+    assert_eq!(it.nth_back(1).map(|g| *g.lense()), Some(1));
+    assert!(it.next().is_none());
+}
+
+#[test]
+fn nth_skips_locks() {
+    let pool = SeekablePool::<u16>::with_capacity(6);
+    let _a = pool.get_mut(1);
+    let _b = pool.get_mut(4);
+    // Neither call locks the slots it jumps over, so the held guards don't end iteration
+    let mut it = pool.iter().skip(2);
+    assert!(it.next().is_some());
+    assert!(it.next().is_some());
+    let stepped: Vec<u16> = pool.iter().step_by(3).map(|guard| *guard.lense()).collect();
+    assert_eq!(stepped.len(), 2);
+    assert!(pool.iter().nth(5).is_some());
+    assert!(pool.iter().nth(6).is_none());
+}
+
+#[test]
+fn ranges() {
+    let mut pool = SeekablePool::<u16>::with_capacity(6);
+    for mut guard in pool.range_mut(2..4) {
+        *guard.lense_mut() = 1;
+    }
+    let values: Vec<u16> = pool.iter().map(|guard| *guard.lense()).collect();
+    assert_eq!(values, [0, 0, 1, 1, 0, 0]);
+    assert_eq!(pool.range(1..5).count(), 4);
+    let slots: Vec<usize> = pool.range(3..6).indexed().map(|(pos, _)| pos).collect();
+    assert_eq!(slots, [3, 4, 5]);
+    assert_eq!(pool.range(6..6).count(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Invalid range! 2..7 for a pool of 6")]
+fn range_out_of_bounds() {
+    let pool = SeekablePool::<u16>::with_capacity(6);
+    pool.range(2..7);
+}
+
+#[test]
+fn sync_ranges() {
+    let pool = SyncPool::<u32>::with_capacity(8);
+    for mut guard in pool.range_mut(4..8).step_by(2) {
+        *guard.lense_mut() = 1;
+    }
+    let values: Vec<u32> = pool.range(2..8).rev().map(|guard| *guard.lense()).collect();
+    assert_eq!(values, [0, 1, 0, 1, 0, 0]);
+}
@@ -0,0 +1,86 @@
+#[macro_use] extern crate lense;
+
+use lense::{IsMut, LenseError, LenseSlice, LenseSliceMut, LockingIter, PoolError};
+
+mk_lense_struct!{pub struct Record:
+    id:    u32,
+    value: u32,
+}
+
+// A u64 backing keeps the bytes 8-byte aligned
+fn bytes(words: &mut [u64]) -> &mut [u8] {
+    unsafe { ::std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, words.len() * 8) }
+}
+
+#[test]
+fn slice_reads_records() {
+    let mut words = [0u64; 2];
+    {
+        let bytes = bytes(&mut words);
+        bytes[0] = 1;
+        bytes[4] = 10;
+        bytes[8] = 2;
+        bytes[12] = 20;
+    }
+    let slice = LenseSlice::<Record<IsMut>>::from_slice(bytes(&mut words)).unwrap();
+    assert_eq!(slice.len(), 2);
+    let values: Vec<(u32, u32)> = slice.iter().map(|r| (*r.id, *r.value)).collect();
+    assert_eq!(values, [(1, 10), (2, 20)]);
+    assert_eq!(*slice.get(1).value, 20);
+    assert_eq!(slice.try_get(2).err(), Some(PoolError::OutOfBounds { pos: 2, len: 2 }));
+    assert_eq!(slice.iter().rev().map(|r| *r.id).collect::<Vec<_>>(), [2, 1]);
+}
+
+#[test]
+fn slice_validates_up_front() {
+    let mut words = [0u64; 2];
+    let bytes = bytes(&mut words);
+    assert_eq!(LenseSlice::<u64>::from_slice(&bytes[..12]).err(),
+               Some(LenseError::Uneven { size: 8, len: 12 }));
+    assert_eq!(LenseSlice::<u32>::from_slice(&bytes[2..10]).err(),
+               Some(LenseError::Misaligned { align: 4 }));
+    assert_eq!(LenseSliceMut::<u16>::from_slice(&mut bytes[1..3]).err(),
+               Some(LenseError::Misaligned { align: 2 }));
+    assert!(LenseSlice::<u64>::from_slice(&bytes[..0]).unwrap().is_empty());
+}
+
+#[test]
+fn slice_mut_locks() {
+    let mut words = [0u64; 2];
+    {
+        let slice = LenseSliceMut::<Record<IsMut>>::from_slice(bytes(&mut words)).unwrap();
+        let mut record = slice.get_mut(1);
+        *record.lense_mut().value = 7;
+        assert_eq!(slice.try_get(1).err(), Some(PoolError::Locked(1)));
+        assert_eq!(slice.iter().skip_locked().count(), 1);
+        drop(record);
+        let shared = slice.get(1);
+        assert_eq!(slice.try_get_mut(1).err(), Some(PoolError::Locked(1)));
+        assert_eq!(*shared.lense().value, 7);
+    }
+    assert_eq!(bytes(&mut words)[12], 7);
+}
+
+#[test]
+fn slice_mut_iter() {
+    let mut words = [0u64; 2];
+    {
+        let mut slice = LenseSliceMut::<u32>::from_slice(bytes(&mut words)).unwrap();
+        for (pos, mut guard) in slice.iter_mut().indexed() {
+            *guard.lense_mut() = pos as u32;
+        }
+        assert_eq!(slice.range(1..4).map(|guard| *guard.lense()).collect::<Vec<_>>(), [1, 2, 3]);
+        slice.as_bytes_mut()[0] = 9;
+        assert_eq!(slice.as_bytes().lense()[0], 9);
+    }
+    assert_eq!(words[1], 3 << 32 | 2);
+}
+
+#[test]
+#[should_panic(expected = "Slice has outstanding mutable guards")]
+fn slice_mut_bytes_while_writing() {
+    let mut words = [0u64; 1];
+    let slice = LenseSliceMut::<u64>::from_slice(bytes(&mut words)).unwrap();
+    let _guard = slice.get_mut(0);
+    slice.as_bytes();
+}
@@ -0,0 +1,76 @@
+#[macro_use] extern crate lense;
+
+use std::thread;
+
+use lense::{SeekablePool, SubPool, IsMut};
+
+mk_lense_struct!{pub struct Job:
+    id:     u32,
+    result: u32,
+}
+
+fn is_send<T: Send>() {}
+
+#[test]
+fn sub_pools_are_send() {
+    is_send::<SubPool<Job<IsMut>>>();
+}
+
+#[test]
+fn split_at_mut() {
+    let mut pool = SeekablePool::<u32>::with_capacity(5);
+    {
+        let (mut head, mut tail) = pool.split_at_mut(2);
+        assert_eq!((head.len(), tail.len()), (2, 3));
+        assert_eq!((head.start(), tail.start()), (0, 2));
+        head.as_mut_slice().copy_from_slice(&[1, 2]);
+        *tail.get_mut(2).unwrap() = 5;
+        let (middle, end) = tail.split_at_mut(1);
+        assert_eq!((middle.start(), end.start(), end.len()), (2, 3, 2));
+    }
+    let values: Vec<u32> = pool.iter().map(|guard| *guard.lense()).collect();
+    assert_eq!(values, [1, 2, 0, 0, 5]);
+}
+
+#[test]
+#[should_panic(expected = "Invalid index! 6 is out of bounds for a pool of 5")]
+fn split_out_of_bounds() {
+    let mut pool = SeekablePool::<u32>::with_capacity(5);
+    pool.split_at_mut(6);
+}
+
+#[test]
+fn chunks_exact_mut_on_threads() {
+    let mut pool = SeekablePool::<Job<IsMut>>::with_capacity(10);
+    let subs: Vec<_> = pool.chunks_exact_mut(3).collect();
+    assert_eq!(subs.len(), 3);
+    thread::scope(|s| for mut sub in subs {
+        s.spawn(move || {
+            let start = sub.start() as u32;
+            for (i, job) in sub.iter_mut().enumerate() {
+                *job.id = start + i as u32;
+                *job.result = *job.id * 2;
+            }
+        });
+    });
+    let results: Vec<u32> = pool.iter().map(|job| *job.lense().result).collect();
+    assert_eq!(results, [0, 2, 4, 6, 8, 10, 12, 14, 16, 0]);
+}
+
+#[test]
+fn par_iter_mut() {
+    let mut pool = SeekablePool::<Job<IsMut>>::with_capacity(101);
+    pool.par_iter_mut(4, |pos, job| {
+        *job.id = pos as u32;
+        *job.result = pos as u32 + 1;
+    });
+    assert!(pool.iter().enumerate().all(|(pos, guard)| {
+        let job = guard.lense();
+        *job.id == pos as u32 && *job.result == pos as u32 + 1
+    }));
+    // More threads than records still covers each record once
+    let mut small = SeekablePool::<u32>::with_capacity(2);
+    small.par_iter_mut(8, |_, x| *x += 1);
+    assert_eq!(small.iter().map(|x| *x.lense()).sum::<u32>(), 2);
+    SeekablePool::<u32>::new().par_iter_mut(2, |_, _| unreachable!());
+}
@@ -0,0 +1,127 @@
+#[macro_use] extern crate lense;
+
+use lense::{FixedStr, IsMut, IsRef, Lense, LenseBytes, LenseError, LenseMut, LenseSlice,
+            LenseStr, LockingIter, PoolError, SeekablePool, SyncPool};
+
+mk_lense_struct!{pub struct Account:
+    name: FixedStr<12>,
+    code: FixedStr<4, b' '>,
+    id:   u32,
+}
+
+mk_lense_struct!{pub struct Message:
+    key:  LenseBytes<u8>,
+    body: LenseStr<u16>,
+}
+
+#[test]
+fn fixed_strings_in_a_pool() {
+    let pool = SeekablePool::<Account<IsMut>>::with_capacity(2);
+    {
+        let mut guard = pool.get_mut(1);
+        let mut account = guard.lense_mut();
+        account.name.set("héllo").unwrap();
+        account.code.set("ab").unwrap();
+        assert_eq!(account.name.capacity(), 12);
+        assert_eq!(account.code.set("abcde"),
+                   Err(LenseError::DoesNotFit { capacity: 4, len: 5 }));
+        *account.id = 7;
+    }
+    let guard = pool.get(1);
+    let account = guard.lense();
+    assert_eq!((account.name, account.code, *account.id), ("héllo", "ab", 7));
+    assert_eq!(&pool.as_bytes().lense()[32..36], b"ab  ");
+    assert_eq!(pool.get(0).lense().name, "");
+}
+
+#[test]
+fn shorter_fixed_string_clears_the_rest() {
+    let mut bytes = [0u8; 20];
+    let mut account = Account::<IsMut>::lense_mut(&mut &mut bytes[..]);
+    account.name.set("long enough!").unwrap();
+    account.name.set("short").unwrap();
+    assert_eq!(&*account.name, "short");
+}
+
+#[test]
+fn only_fixed_strings_have_a_fixed_size() {
+    let fixed = [
+        Account::<IsRef>::FIXED_SIZE,
+        Message::<IsRef>::FIXED_SIZE,
+        LenseBytes::<u8>::FIXED_SIZE,
+    ];
+    assert_eq!(fixed, [true, false, false]);
+}
+
+#[test]
+fn prefixed_strings() {
+    let mut bytes = [0u8; 9];
+    bytes[..3].copy_from_slice(b"\x02k1");
+    bytes[3..5].copy_from_slice(&4u16.to_ne_bytes());
+    bytes[5..].copy_from_slice(b"body");
+    let mut message = Message::<IsMut>::lense_mut(&mut &mut bytes[..]);
+    message.key.copy_from_slice(b"k2");
+    assert_eq!(message.body.set("text"), Ok(()));
+    assert_eq!(message.body.set("longer"), Err(LenseError::DoesNotFit { capacity: 4, len: 6 }));
+    let message = Message::<IsRef>::lense(&mut &bytes[..]);
+    assert_eq!((message.key, message.body), (&b"k2"[..], "text"));
+}
+
+#[test]
+fn invalid_utf8_is_an_error() {
+    let mut bytes = *b"\x01k\0\0\xff\xfe";
+    bytes[2..4].copy_from_slice(&2u16.to_ne_bytes());
+    assert_eq!(Message::<IsRef>::try_lense(&mut &bytes[..]).err(),
+               Some(LenseError::InvalidUtf8 { valid_up_to: 0 }));
+    assert_eq!(FixedStr::<4>::try_lense(&mut &b"ok\xff\0"[..]).err(),
+               Some(LenseError::InvalidUtf8 { valid_up_to: 2 }));
+}
+
+#[test]
+fn rewrite_keeps_strings_valid() {
+    let pool = SeekablePool::<Account<IsMut>>::with_capacity(1);
+    let mut guard = pool.get_mut(0);
+    guard.lense_mut().name.set("abc").unwrap();
+
+    // A panic half way through puts the record back
+    let result = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| {
+        guard.rewrite(|bytes| {
+            bytes[0] = 0xff;
+            panic!("interrupted");
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(guard.lense().name, "abc");
+
+    // So do bytes the lense rejects
+    assert_eq!(guard.rewrite(|bytes| bytes[1] = 0xff),
+               Err(LenseError::InvalidUtf8 { valid_up_to: 1 }));
+    assert_eq!(guard.lense().name, "abc");
+
+    guard.rewrite(|bytes| bytes[0] = b'x').unwrap();
+    assert_eq!(guard.lense().name, "xbc");
+}
+
+#[test]
+fn invalid_records_are_pool_errors() {
+    let invalid = Some(PoolError::Lense(LenseError::InvalidUtf8 { valid_up_to: 0 }));
+    let mut pool = SeekablePool::<Account<IsMut>>::with_capacity(2);
+    pool.as_bytes_mut()[20] = 0xff;
+    assert_eq!(pool.try_get(1).err(), invalid);
+    assert_eq!(pool.try_get_mut(1).err(), invalid);
+    assert_eq!(pool.iter().report_locked().nth(1).and_then(Result::err), invalid);
+    assert_eq!(LenseSlice::<Account<IsMut>>::from_slice(pool.as_bytes().lense()).unwrap()
+                   .try_get(1).err(), invalid);
+
+    // Failing leaves the record unlocked
+    pool.as_bytes_mut()[20] = b'a';
+    assert_eq!(pool.get_mut(1).lense().name, "a");
+
+    let mut sync = SyncPool::<Account<IsMut>>::with_capacity(1);
+    sync.get_mut(0).lense_mut().name.set("abc").unwrap();
+    sync.as_bytes_mut()[0] = 0xff;
+    assert_eq!(sync.try_get(0).err(), invalid);
+    assert_eq!(sync.try_get_mut(0).err(), invalid);
+    sync.as_bytes_mut()[0] = b'x';
+    assert_eq!(sync.get_mut(0).lense().name, "xbc");
+}
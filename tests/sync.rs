@@ -0,0 +1,87 @@
+#[macro_use] extern crate lense;
+
+use std::thread;
+use std::time::Duration;
+
+use lense::{SyncPool, PoolError, IsMut};
+
+mk_lense_struct!{pub struct Counter:
+    hits:  u64,
+    total: u64,
+}
+
+fn is_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn sync_pool_is_send_and_sync() {
+    is_send_sync::<SyncPool<Counter<IsMut>>>();
+}
+
+#[test]
+fn blocking_contention() {
+    let pool = SyncPool::<Counter<IsMut>>::with_capacity(2);
+    thread::scope(|s| {
+        for t in 0..8u64 {
+            let pool = &pool;
+            s.spawn(move || for _ in 0..1000 {
+                let mut guard = pool.get_mut((t % 2) as usize);
+                let counter = guard.lense_mut();
+                *counter.hits += 1;
+                *counter.total += t;
+            });
+        }
+    });
+    let (even, odd) = (pool.get(0), pool.get(1));
+    let (even, odd) = (even.lense(), odd.lense());
+    assert_eq!((*even.hits, *even.total), (4000, (2 + 4 + 6) * 1000));
+    assert_eq!((*odd.hits, *odd.total), (4000, (1 + 3 + 5 + 7) * 1000));
+}
+
+#[test]
+fn spinning_contention() {
+    let pool = SyncPool::<u32>::with_capacity(1);
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| for _ in 0..1000 {
+                *pool.spin_get_mut(0).lense_mut() += 1;
+                assert!(*pool.spin_get(0).lense() > 0);
+            });
+        }
+    });
+    assert_eq!(*pool.try_get(0).unwrap().lense(), 4000);
+}
+
+#[test]
+fn try_get_across_threads() {
+    let pool = SyncPool::<u32>::with_capacity(1);
+    let guard = pool.try_get_mut(0).unwrap();
+    thread::scope(|s| {
+        s.spawn(|| {
+            assert_eq!(pool.try_get(0).err(), Some(PoolError::Locked(0)));
+            assert_eq!(pool.try_get_mut(0).err(), Some(PoolError::Locked(0)));
+            assert_eq!(pool.try_get(1).err(), Some(PoolError::OutOfBounds { pos: 1, len: 1 }));
+        });
+    });
+    drop(guard);
+
+    let readers: Vec<_> = (0..3).map(|_| pool.try_get(0).unwrap()).collect();
+    thread::scope(|s| {
+        s.spawn(|| assert!(pool.try_get(0).is_ok()));
+        s.spawn(|| assert_eq!(pool.try_get_mut(0).err(), Some(PoolError::Locked(0))));
+    });
+    drop(readers);
+    assert!(pool.try_get_mut(0).is_ok());
+}
+
+#[test]
+fn blocked_writer_wakes_on_release() {
+    let pool = SyncPool::<u32>::with_capacity(1);
+    let reader = pool.get(0);
+    thread::scope(|s| {
+        let writer = s.spawn(|| *pool.get_mut(0).lense_mut() = 7);
+        thread::sleep(Duration::from_millis(20));
+        assert!(!writer.is_finished());
+        drop(reader);
+    });
+    assert_eq!(*pool.get(0).lense(), 7);
+}
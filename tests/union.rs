@@ -0,0 +1,66 @@
+#[macro_use] extern crate lense;
+
+use lense::{IsMut, IsRef, Le, Lense, SeekablePool};
+
+mk_lense_union!{pub union Value:
+    as_int, as_int_mut: Le<u32>,
+    as_float, as_float_mut: Le<f32>,
+    as_pair, as_pair_mut: (u8, u8),
+    as_bytes, as_bytes_mut: [u8; 6],
+}
+
+mk_lense_struct!{pub struct Field:
+    kind:  u8,
+    value: Value<'static, IsMut>,
+}
+
+#[test]
+fn size_of_largest_member() {
+    let padded = cfg!(feature = "automatic_padding");
+    assert_eq!(Value::<IsRef>::ALIGN, 4);
+    assert_eq!(Value::<IsRef>::SIZE, if padded { 8 } else { 6 });
+    assert_eq!(Field::<IsRef>::SIZE, if padded { 12 } else { 7 });
+}
+
+#[test]
+#[cfg(feature = "automatic_padding")]
+fn earlier_field_picks_the_member() {
+    let pool = SeekablePool::<Field<IsMut>>::with_capacity(2);
+    {
+        let mut guard = pool.get_mut(0);
+        let mut field = guard.lense_mut();
+        *field.kind = 0;
+        field.value.as_int_mut().set(7);
+    }
+    {
+        let mut guard = pool.get_mut(1);
+        let mut field = guard.lense_mut();
+        *field.kind = 1;
+        field.value.as_float_mut().set(1.5);
+    }
+    let read = |pos| {
+        let guard = pool.get(pos);
+        let field = guard.lense();
+        match *field.kind {
+            0 => field.value.as_int().get() as f32,
+            _ => field.value.as_float().get(),
+        }
+    };
+    assert_eq!((read(0), read(1)), (7.0, 1.5));
+}
+
+#[test]
+fn members_share_the_slot() {
+    let pool = SeekablePool::<Value<IsMut>>::with_capacity(1);
+    {
+        let mut guard = pool.get_mut(0);
+        let mut value = guard.lense_mut();
+        *value.as_pair_mut().1 = 1;
+        *value.as_bytes_mut()[5] = 9;
+        assert_eq!(value.as_int().get(), 0x100);
+    }
+    let guard = pool.get(0);
+    let value = guard.lense();
+    assert_eq!(value.as_bytes().map(|b| *b), [0, 1, 0, 0, 0, 9]);
+    assert_eq!(value.as_pair(), (&0, &1));
+}
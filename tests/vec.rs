@@ -0,0 +1,73 @@
+#[macro_use] extern crate lense;
+
+use lense::{Be, IsMut, IsRef, Lense, LenseError, LenseMut, LenseVec};
+
+mk_lense_struct!{pub struct Packet:
+    kind:    u8,
+    payload: LenseVec<u8, u8>,
+}
+
+mk_lense_struct!{pub struct Route:
+    hops: LenseVec<Be<u32>, u32>,
+}
+
+#[test]
+fn variable_length_records() {
+    let fixed = [
+        Packet::<IsRef>::FIXED_SIZE,
+        <(u8, Route<IsRef>)>::FIXED_SIZE,
+        <[(u8, Be<u32>); 2]>::FIXED_SIZE,
+    ];
+    assert_eq!(fixed, [false, false, true]);
+}
+
+#[test]
+fn walk_variable_records() {
+    let bytes = [1, 2, 10, 20, 2, 0, 3, 3, 30, 31, 32];
+    let mut rest = &bytes[..];
+    let mut seen = Vec::new();
+    while !rest.is_empty() {
+        let size = Packet::<IsRef>::try_size_at(rest).unwrap();
+        let packet = Packet::<IsRef>::lense(&mut &rest[..size]);
+        seen.push((*packet.kind, packet.payload.iter().copied().collect::<Vec<_>>()));
+        rest = &rest[size..];
+    }
+    assert_eq!(seen, [(1, vec![10, 20]), (2, vec![]), (3, vec![30, 31, 32])]);
+}
+
+#[test]
+fn mutate_items_in_place() {
+    let mut bytes = [0u8; 5];
+    bytes[1] = 3;
+    {
+        let mut packet = Packet::<IsMut>::lense_mut(&mut &mut bytes[..]);
+        for (i, b) in packet.payload.iter_mut().enumerate() {
+            *b = i as u8 + 1;
+        }
+        assert_eq!(packet.payload.as_mut_slice(), [1, 2, 3]);
+    }
+    assert_eq!(bytes, [0, 3, 1, 2, 3]);
+}
+
+#[test]
+fn endian_prefix() {
+    let mut words = [0u32; 3];
+    {
+        let bytes = unsafe {
+            ::std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, 12)
+        };
+        bytes[3] = 2;
+        let mut route = Route::<IsMut>::lense_mut(&mut &mut bytes[..]);
+        assert_eq!(route.hops.len(), 2);
+        *route.hops.get_mut(1).unwrap() = 7;
+        assert_eq!(Route::<IsRef>::try_size_at(bytes), Ok(12));
+    }
+    assert_eq!(words[2], 7);
+}
+
+#[test]
+fn truncated_items() {
+    let bytes = [0, 4, 1, 2];
+    assert_eq!(Packet::<IsRef>::try_size_at(&bytes),
+               Err(LenseError::Truncated { needed: 4, available: 2 }));
+}